@@ -11,7 +11,9 @@
 
 use std::borrow::Cow;
 use std::fmt::Debug;
+use std::iter::Enumerate;
 use std::mem::{replace, take};
+use std::rc::Rc;
 
 #[cfg(test)]
 mod test;
@@ -41,6 +43,12 @@ pub enum GraphemeSource {
 pub struct FormattedGrapheme<'a> {
     pub grapheme: Grapheme<'a>,
     pub source: GraphemeSource,
+    /// Set on the first grapheme of a visual line produced by softwrapping (the wrap indicator
+    /// grapheme itself if `TextFormat::wrap_indicator` is non-empty, otherwise the first real
+    /// grapheme of the wrapped word). This lets consumers detect a soft-wrap break exactly,
+    /// rather than inferring it by watching `Position::row` jump - which is fragile once virtual
+    /// text can also advance `row` without a softwrap occurring.
+    pub is_wrap_boundary: bool,
 }
 
 impl<'a> FormattedGrapheme<'a> {
@@ -53,6 +61,7 @@ pub fn new(
         FormattedGrapheme {
             grapheme: Grapheme::new(g, visual_x, tab_width),
             source,
+            is_wrap_boundary: false,
         }
     }
     /// Returns whether this grapheme is virtual inline text
@@ -60,10 +69,20 @@ pub fn is_virtual(&self) -> bool {
         matches!(self.source, GraphemeSource::VirtualText { .. })
     }
 
+    /// Returns whether this is the synthetic grapheme emitted once the document is exhausted
+    /// (required for rendering and correct position computations at EOF). This is the only
+    /// grapheme with `doc_chars() == 0` and a [`GraphemeSource::Document`] source, since real
+    /// document graphemes always consume at least one codepoint and virtual text is tagged
+    /// [`GraphemeSource::VirtualText`] instead.
+    pub fn is_eof(&self) -> bool {
+        matches!(self.source, GraphemeSource::Document { codepoints: 0 })
+    }
+
     pub fn placeholder() -> Self {
         FormattedGrapheme {
             grapheme: Grapheme::Other { g: " ".into() },
             source: GraphemeSource::Document { codepoints: 0 },
+            is_wrap_boundary: false,
         }
     }
 
@@ -92,10 +111,40 @@ pub struct TextFormat {
     pub soft_wrap: bool,
     pub tab_width: u16,
     pub max_wrap: u16,
+    /// When set, overrides `max_wrap` for the split-vs-wrap decision in `advance_to_next_word`,
+    /// expressing the threshold as a percentage of `viewport_width` instead of a fixed column
+    /// count. A fixed `max_wrap` looks increasingly stingy as the viewport gets wider (words a
+    /// few columns from the edge get force-split even though there's plenty of room to wrap them
+    /// whole); a percentage scales with the viewport instead. `None` preserves the `max_wrap`
+    /// behavior.
+    pub max_wrap_percentage: Option<u8>,
     pub max_indent_retain: u16,
     pub wrap_indicator: Box<str>,
     pub wrap_indicator_highlight: Option<Highlight>,
     pub viewport_width: u16,
+    /// When softwrapping, treat a hyphen as a preferred wrap point (breaking immediately after
+    /// it) instead of leaving it attached to the following word. Off by default since this would
+    /// otherwise be a surprising change to how hyphenated identifiers wrap while code editing;
+    /// intended to be enabled for prose (e.g. markdown) buffers.
+    pub hyphen_break: bool,
+    /// When softwrapping, let trailing whitespace that lands exactly at the wrap point
+    /// spill past the edge of the current visual line instead of being carried into the
+    /// indent of the next one. Off by default since it lets a softwrapped line exceed
+    /// `viewport_width`, which position computations (for example cursor placement during
+    /// normal editing) assume never happens; useful for whitespace-rendering and for APIs that
+    /// need to map visual positions back to the exact source text.
+    pub preserve_trailing_whitespace: bool,
+}
+
+impl TextFormat {
+    /// The effective word-split threshold used by `advance_to_next_word`: `max_wrap_percentage`
+    /// of `viewport_width` if set, otherwise the fixed `max_wrap`.
+    fn max_wrap(&self) -> usize {
+        match self.max_wrap_percentage {
+            Some(percentage) => self.viewport_width as usize * percentage as usize / 100,
+            None => self.max_wrap as usize,
+        }
+    }
 }
 
 // test implementation is basically only used for testing or when softwrap is always disabled
@@ -105,10 +154,74 @@ fn default() -> Self {
             soft_wrap: false,
             tab_width: 4,
             max_wrap: 3,
+            max_wrap_percentage: None,
             max_indent_retain: 4,
             wrap_indicator: Box::from(" "),
             viewport_width: 17,
             wrap_indicator_highlight: None,
+            hyphen_break: false,
+            preserve_trailing_whitespace: false,
+        }
+    }
+}
+
+/// Whether `grapheme` is a plain ASCII hyphen, the only character `hyphen_break` treats
+/// specially.
+fn is_hyphen(grapheme: &FormattedGrapheme) -> bool {
+    matches!(&grapheme.grapheme, Grapheme::Other { g } if &**g == "-")
+}
+
+/// A cache of block boundaries found by [`DocumentFormatter::new_at_prev_checkpoint_cached`],
+/// keyed by document revision. Intended to be owned across renders (for example by a `View`)
+/// so that repeatedly formatting nearby positions, as happens while scrolling through a large
+/// document, does not repeat the search for the enclosing block's start on every call.
+///
+/// The cache is cleared entirely whenever it observes a new revision, since a single edit can
+/// invalidate every checkpoint recorded after the edit point.
+#[derive(Debug, Default, Clone)]
+pub struct FormatterCache {
+    revision: usize,
+    /// `(block_char_idx, block_line_idx)` pairs, sorted by `block_char_idx`.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl FormatterCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops all cached checkpoints if `revision` does not match the revision the cache was
+    /// last populated for.
+    fn sync(&mut self, revision: usize) {
+        if self.revision != revision {
+            self.checkpoints.clear();
+            self.revision = revision;
+        }
+    }
+
+    /// Returns the cached `(block_char_idx, block_line_idx)` checkpoint closest to, but not
+    /// after, `char_idx`, if one has been recorded.
+    fn nearest_checkpoint(&self, char_idx: usize) -> Option<(usize, usize)> {
+        match self.checkpoints.binary_search_by_key(&char_idx, |&(idx, _)| idx) {
+            Ok(i) => Some(self.checkpoints[i]),
+            Err(0) => None,
+            Err(i) => Some(self.checkpoints[i - 1]),
+        }
+    }
+
+    /// Records a checkpoint, keeping `checkpoints` sorted by `block_char_idx`. Bounded so that
+    /// scrolling through an arbitrarily large document does not grow the cache without limit.
+    fn insert(&mut self, block_char_idx: usize, block_line_idx: usize) {
+        const MAX_CHECKPOINTS: usize = 512;
+        match self
+            .checkpoints
+            .binary_search_by_key(&block_char_idx, |&(idx, _)| idx)
+        {
+            Ok(i) => self.checkpoints[i].1 = block_line_idx,
+            Err(i) => self.checkpoints.insert(i, (block_char_idx, block_line_idx)),
+        }
+        if self.checkpoints.len() > MAX_CHECKPOINTS {
+            self.checkpoints.remove(0);
         }
     }
 }
@@ -126,11 +239,18 @@ pub struct DocumentFormatter<'t> {
     /// The line pos of the `graphemes` iter used for inserting annotations
     line_pos: usize,
     exhausted: bool,
+    /// Whether to yield the synthetic EOF grapheme (see [`FormattedGrapheme::is_eof`]) once the
+    /// document is exhausted. Rendering needs it for cursor positioning at EOF, but consumers
+    /// like width-measuring or hard-wrap point computation don't want to see a grapheme with no
+    /// backing document text and would otherwise have to special-case `doc_chars() == 0`
+    /// themselves; [`Self::without_eof_grapheme`] lets them opt out instead.
+    emit_eof_grapheme: bool,
 
     /// Line breaks to be reserved for virtual text
     /// at the next line break
     virtual_lines: usize,
-    inline_anntoation_graphemes: Option<(Graphemes<'t>, Option<Highlight>)>,
+    inline_anntoation_graphemes:
+        Option<(Enumerate<Graphemes<'t>>, Option<Highlight>, Option<Rc<[Option<Highlight>]>>)>,
 
     // softwrap specific
     /// The indentation of the current line
@@ -161,6 +281,49 @@ pub fn new_at_prev_checkpoint(
         // TODO divide long lines into blocks to avoid bad performance for long lines
         let block_line_idx = text.char_to_line(char_idx.min(text.len_chars()));
         let block_char_idx = text.line_to_char(block_line_idx);
+        Self::at_block_start(text, text_fmt, annotations, block_char_idx, block_line_idx)
+    }
+
+    /// Like [`Self::new_at_prev_checkpoint`], except that the (potentially expensive) search
+    /// for the block boundary preceding `char_idx` is skipped if `cache` already has a
+    /// checkpoint at or before `char_idx` for the given document `revision`: formatting then
+    /// starts from the nearest such checkpoint, which is a line start but not necessarily the
+    /// one `new_at_prev_checkpoint` would pick.
+    /// The checkpoint used to satisfy (or populate) this call is left in `cache` so that a
+    /// caller repeatedly formatting nearby positions, such as a `View` scrolling through a
+    /// large document, only pays for the search once per neighbourhood.
+    ///
+    /// `cache` is wholesale invalidated whenever `revision` changes, since an edit can shift
+    /// every checkpoint recorded after the edit point.
+    pub fn new_at_prev_checkpoint_cached(
+        text: RopeSlice<'t>,
+        text_fmt: &'t TextFormat,
+        annotations: &'t TextAnnotations,
+        char_idx: usize,
+        cache: &mut FormatterCache,
+        revision: usize,
+    ) -> (Self, usize) {
+        cache.sync(revision);
+        let char_idx = char_idx.min(text.len_chars());
+        let (block_char_idx, block_line_idx) = match cache.nearest_checkpoint(char_idx) {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let block_line_idx = text.char_to_line(char_idx);
+                let block_char_idx = text.line_to_char(block_line_idx);
+                cache.insert(block_char_idx, block_line_idx);
+                (block_char_idx, block_line_idx)
+            }
+        };
+        Self::at_block_start(text, text_fmt, annotations, block_char_idx, block_line_idx)
+    }
+
+    fn at_block_start(
+        text: RopeSlice<'t>,
+        text_fmt: &'t TextFormat,
+        annotations: &'t TextAnnotations,
+        block_char_idx: usize,
+        block_line_idx: usize,
+    ) -> (Self, usize) {
         annotations.reset_pos(block_char_idx);
         (
             DocumentFormatter {
@@ -170,6 +333,7 @@ pub fn new_at_prev_checkpoint(
                 graphemes: RopeGraphemes::new(text.slice(block_char_idx..)),
                 char_pos: block_char_idx,
                 exhausted: false,
+                emit_eof_grapheme: true,
                 virtual_lines: 0,
                 indent_level: None,
                 peeked_grapheme: None,
@@ -184,10 +348,14 @@ pub fn new_at_prev_checkpoint(
 
     fn next_inline_annotation_grapheme(&mut self) -> Option<(&'t str, Option<Highlight>)> {
         loop {
-            if let Some(&mut (ref mut annotation, highlight)) =
+            if let Some((ref mut annotation, highlight, ref styles)) =
                 self.inline_anntoation_graphemes.as_mut()
             {
-                if let Some(grapheme) = annotation.next() {
+                if let Some((idx, grapheme)) = annotation.next() {
+                    let highlight = styles
+                        .as_ref()
+                        .and_then(|styles| styles.get(idx).copied().flatten())
+                        .or(*highlight);
                     return Some((grapheme, highlight));
                 }
             }
@@ -196,8 +364,9 @@ fn next_inline_annotation_grapheme(&mut self) -> Option<(&'t str, Option<Highlig
                 self.annotations.next_inline_annotation_at(self.char_pos)
             {
                 self.inline_anntoation_graphemes = Some((
-                    UnicodeSegmentation::graphemes(&*annotation.text, true),
+                    UnicodeSegmentation::graphemes(&*annotation.text, true).enumerate(),
                     highlight,
+                    annotation.styles.clone(),
                 ))
             } else {
                 return None;
@@ -205,6 +374,13 @@ fn next_inline_annotation_grapheme(&mut self) -> Option<(&'t str, Option<Highlig
         }
     }
 
+    /// `col` is the running visual column (`self.visual_pos.col`, or a word's start column while
+    /// mid-word-wrap), not a document column - it already includes the width of any inline
+    /// annotation graphemes emitted earlier in the same line via `next_inline_annotation_grapheme`,
+    /// since those are counted into `self.visual_pos.col` by `next` just like document graphemes
+    /// are. A tab is sized against whichever grapheme `col` belongs to, so a tab following an
+    /// inlay hint lands on the correct visual tab stop rather than one computed as if the hint
+    /// weren't there.
     fn advance_grapheme(&mut self, col: usize) -> Option<FormattedGrapheme<'t>> {
         let (grapheme, source) =
             if let Some((grapheme, highlight)) = self.next_inline_annotation_grapheme() {
@@ -219,18 +395,39 @@ fn advance_grapheme(&mut self, col: usize) -> Option<FormattedGrapheme<'t>> {
                     None => Cow::from(grapheme).into(),
                 };
 
+                let start = self.char_pos;
                 self.char_pos += codepoints as usize;
+
+                // The grapheme cluster starting at `start` is fully consumed now, so this is the
+                // correct point to insert an `AnnotationAnchor::After` annotation anchored there -
+                // unlike anchoring at `start + 1`, this can't land inside `start`'s own cluster
+                // (e.g. between a base character and a combining mark) and works just as well when
+                // `start` is the last character on the line.
+                if let Some((annotation, highlight)) =
+                    self.annotations.next_after_inline_annotation_at(start)
+                {
+                    self.inline_anntoation_graphemes = Some((
+                        UnicodeSegmentation::graphemes(&*annotation.text, true).enumerate(),
+                        highlight,
+                        annotation.styles.clone(),
+                    ));
+                }
+
                 (grapheme, GraphemeSource::Document { codepoints })
             } else {
                 if self.exhausted {
                     return None;
                 }
                 self.exhausted = true;
+                if !self.emit_eof_grapheme {
+                    return None;
+                }
                 // EOF grapheme is required for rendering
                 // and correct position computations
                 return Some(FormattedGrapheme {
                     grapheme: Grapheme::Other { g: " ".into() },
                     source: GraphemeSource::Document { codepoints: 0 },
+                    is_wrap_boundary: false,
                 });
             };
 
@@ -282,9 +479,35 @@ fn wrap_word(&mut self, virtual_lines_before_word: usize) -> usize {
                 .change_position(visual_x, self.text_fmt.tab_width);
             word_width += grapheme.width();
         }
+
+        // Mark the very first grapheme of the wrapped line: the wrap indicator if one was
+        // spliced in above, otherwise the first real grapheme of the word itself (fetched ahead
+        // into `peeked_grapheme` when no grapheme of the word has been buffered yet).
+        if let Some(first) = self.word_buf.first_mut() {
+            first.is_wrap_boundary = true;
+        } else {
+            self.peek_grapheme(self.visual_pos.col);
+            if let Some((first, _)) = self.peeked_grapheme.as_mut() {
+                first.is_wrap_boundary = true;
+            }
+        }
+
         word_width
     }
 
+    /// Fetches the next grapheme without consuming it permanently: it is stashed in
+    /// `peeked_grapheme` so the normal fetch step later in `advance_to_next_word` picks it up.
+    fn peek_grapheme(&mut self, col: usize) -> Option<&FormattedGrapheme<'t>> {
+        if self.peeked_grapheme.is_none() {
+            let virtual_lines_before_grapheme = self.virtual_lines;
+            let grapheme = self.advance_grapheme(col)?;
+            self.peeked_grapheme =
+                Some((grapheme, self.virtual_lines - virtual_lines_before_grapheme));
+            self.virtual_lines = virtual_lines_before_grapheme;
+        }
+        self.peeked_grapheme.as_ref().map(|(grapheme, _)| grapheme)
+    }
+
     fn advance_to_next_word(&mut self) {
         self.word_buf.clear();
         let mut word_width = 0;
@@ -294,22 +517,35 @@ fn advance_to_next_word(&mut self) {
         loop {
             // softwrap word if necessary
             if word_width + self.visual_pos.col >= self.text_fmt.viewport_width as usize {
-                // wrapping this word would move too much text to the next line
-                // split the word at the line end instead
-                if word_width > self.text_fmt.max_wrap as usize {
-                    // Usually we stop accomulating graphemes as soon as softwrapping becomes necessary.
-                    // However if the last grapheme is multiple columns wide it might extend beyond the EOL.
-                    // The condition below ensures that this grapheme is not cutoff and instead wrapped to the next line
-                    if word_width + self.visual_pos.col > self.text_fmt.viewport_width as usize {
-                        self.peeked_grapheme = self.word_buf.pop().map(|grapheme| {
-                            (grapheme, self.virtual_lines - virtual_lines_before_grapheme)
-                        });
-                        self.virtual_lines = virtual_lines_before_grapheme;
+                // A run of trailing whitespace that happens to land right at the wrap point
+                // would otherwise be carried into the indent of the next line below. Let it
+                // spill past the edge of the current line instead, so it stays visible where
+                // it was typed rather than being misplaced.
+                let trailing_whitespace = word_width == 0
+                    && self.text_fmt.preserve_trailing_whitespace
+                    && self
+                        .peek_grapheme(self.visual_pos.col)
+                        .map_or(false, |grapheme| grapheme.is_whitespace());
+
+                if !trailing_whitespace {
+                    // wrapping this word would move too much text to the next line
+                    // split the word at the line end instead
+                    if word_width > self.text_fmt.max_wrap() {
+                        // Usually we stop accomulating graphemes as soon as softwrapping becomes necessary.
+                        // However if the last grapheme is multiple columns wide it might extend beyond the EOL.
+                        // The condition below ensures that this grapheme is not cutoff and instead wrapped to the next line
+                        if word_width + self.visual_pos.col > self.text_fmt.viewport_width as usize
+                        {
+                            self.peeked_grapheme = self.word_buf.pop().map(|grapheme| {
+                                (grapheme, self.virtual_lines - virtual_lines_before_grapheme)
+                            });
+                            self.virtual_lines = virtual_lines_before_grapheme;
+                        }
+                        return;
                     }
-                    return;
-                }
 
-                word_width = self.wrap_word(virtual_lines_before_word);
+                    word_width = self.wrap_word(virtual_lines_before_word);
+                }
             }
 
             virtual_lines_before_grapheme = self.virtual_lines;
@@ -330,7 +566,11 @@ fn advance_to_next_word(&mut self) {
                 self.indent_level = None;
             }
 
-            let is_word_boundary = grapheme.is_word_boundary();
+            let is_word_boundary = if self.text_fmt.hyphen_break {
+                grapheme.is_word_boundary() || is_hyphen(&grapheme)
+            } else {
+                grapheme.is_word_boundary() && !is_hyphen(&grapheme)
+            };
             word_width += grapheme.width();
             self.word_buf.push(grapheme);
 
@@ -349,6 +589,22 @@ pub fn line_pos(&self) -> usize {
     pub fn visual_pos(&self) -> Position {
         self.visual_pos
     }
+
+    /// Suppresses the synthetic EOF grapheme that would otherwise be yielded once the document
+    /// is exhausted. For consumers that only care about real document/virtual-text graphemes
+    /// (width measurement, hard-wrap point computation) and would otherwise have to filter out
+    /// `FormattedGrapheme::is_eof` themselves. Rendering still wants the EOF grapheme for cursor
+    /// positioning, so this is opt-in rather than the default.
+    pub fn without_eof_grapheme(mut self) -> Self {
+        self.emit_eof_grapheme = false;
+        self
+    }
+
+    /// Returns the indentation level of the current line, or `None` if the line's indentation
+    /// level is not yet known because no non-whitespace grapheme has been encountered on it yet.
+    pub fn current_indent_level(&self) -> Option<usize> {
+        self.indent_level
+    }
 }
 
 impl<'t> Iterator for DocumentFormatter<'t> {
@@ -367,6 +623,16 @@ fn next(&mut self) -> Option<Self::Item> {
             self.word_i += 1;
             grapheme
         } else {
+            // On a very long non-wrapped line, `render_text` stops drawing once `pos.col`
+            // passes the right edge, but this still calls `advance_grapheme` once per remaining
+            // document grapheme up to the next newline - there is no cheap way to jump straight
+            // to it instead. `RopeGraphemes`/`graphemes` itself could be seeked ahead with
+            // `text.line_to_char(line + 1)`, but `self.annotations` (overlays, inline
+            // annotations) is a stateful iterator expected to be queried once per char position
+            // in order; skipping the intervening positions would desync it from `char_pos`
+            // rather than actually save the per-grapheme annotation lookups, which is where
+            // most of the cost is. See the block-splitting TODO on
+            // `new_at_prev_checkpoint` for the same underlying long-line problem.
             self.advance_grapheme(self.visual_pos.col)?
         };
 
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use crate::doc_formatter::{DocumentFormatter, TextFormat};
+use crate::doc_formatter::{DocumentFormatter, FormatterCache, TextFormat};
 use crate::text_annotations::{InlineAnnotation, Overlay, TextAnnotations};
 
 impl TextFormat {
@@ -9,11 +9,35 @@ fn new_test(softwrap: bool) -> Self {
             soft_wrap: softwrap,
             tab_width: 2,
             max_wrap: 3,
+            max_wrap_percentage: None,
             max_indent_retain: 4,
             wrap_indicator: ".".into(),
             wrap_indicator_highlight: None,
             // use a prime number to allow lining up too often with repeat
             viewport_width: 17,
+            hyphen_break: false,
+            preserve_trailing_whitespace: false,
+        }
+    }
+
+    fn new_test_hyphenated() -> Self {
+        TextFormat {
+            hyphen_break: true,
+            ..Self::new_test(true)
+        }
+    }
+
+    fn new_test_preserve_trailing_whitespace() -> Self {
+        TextFormat {
+            preserve_trailing_whitespace: true,
+            ..Self::new_test(true)
+        }
+    }
+
+    fn new_test_max_wrap_percentage(percentage: u8) -> Self {
+        TextFormat {
+            max_wrap_percentage: Some(percentage),
+            ..Self::new_test(true)
         }
     }
 }
@@ -69,6 +93,128 @@ fn basic_softwrap() {
     assert_eq!(softwrap_text("\t\txxxx1xxxx2xx\n"), "    xxxx1xxxx2xx \n ");
 }
 
+#[test]
+fn wrap_boundary_is_marked_on_the_wrap_indicator_grapheme() {
+    let text_fmt = TextFormat::new_test(true); // wrap_indicator = "."
+    let annotations = TextAnnotations::default();
+    let text = "foo ".repeat(10);
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.as_str().into(), &text_fmt, &annotations, 0);
+
+    let boundaries: Vec<_> = formatter
+        .filter(|(grapheme, _)| grapheme.is_wrap_boundary)
+        .map(|(grapheme, pos)| (grapheme.grapheme.to_string(), pos.row))
+        .collect();
+    // two wraps happen for this input (see `basic_softwrap`), each marked on the "."
+    // wrap-indicator grapheme at the start of its visual line.
+    assert_eq!(boundaries, vec![(".".to_string(), 1), (".".to_string(), 2)]);
+}
+
+#[test]
+fn wrap_boundary_is_marked_even_without_a_wrap_indicator() {
+    let text_fmt = TextFormat {
+        wrap_indicator: "".into(),
+        ..TextFormat::new_test(true)
+    };
+    let annotations = TextAnnotations::default();
+    let text = "foo ".repeat(10);
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.as_str().into(), &text_fmt, &annotations, 0);
+
+    let boundaries: Vec<_> = formatter
+        .filter(|(grapheme, _)| grapheme.is_wrap_boundary)
+        .map(|(grapheme, pos)| (grapheme.grapheme.to_string(), pos.row))
+        .collect();
+    // with no indicator text to splice in, the boundary lands on the first real grapheme of
+    // the wrapped word instead.
+    assert_eq!(boundaries, vec![("f".to_string(), 1), ("f".to_string(), 2)]);
+}
+
+fn softwrap_text_with_max_wrap_percentage(text: &str, percentage: u8) -> String {
+    DocumentFormatter::new_at_prev_checkpoint(
+        text.into(),
+        &TextFormat::new_test_max_wrap_percentage(percentage),
+        &TextAnnotations::default(),
+        0,
+    )
+    .0
+    .collect_to_str()
+}
+
+#[test]
+fn max_wrap_percentage_matches_the_equivalent_fixed_max_wrap() {
+    // the 17-column test viewport makes 20% resolve to the same threshold as the default fixed
+    // `max_wrap` of 3 (`17 * 20 / 100 == 3`), so the two must split long words identically.
+    let text = "\t\txxxx1xxxx2xxxx3xxxx4xxxx5xxxx6xxxx7xxxx8xxxx9xxx\n";
+    assert_eq!(
+        softwrap_text_with_max_wrap_percentage(text, 20),
+        softwrap_text(text)
+    );
+}
+
+#[test]
+fn max_wrap_percentage_can_avoid_a_split_that_a_fixed_max_wrap_would_force() {
+    let text = "xx hyphenated-word\n";
+    // the default fixed `max_wrap` (3) is tiny relative to the 17-column viewport, so
+    // "hyphenated-word" gets force-split mid-word once it reaches the edge.
+    assert!(!softwrap_text(text).contains("hyphenated-word"));
+    // a percentage large enough relative to the viewport lets the whole word wrap to the next
+    // line intact instead of being split.
+    assert!(softwrap_text_with_max_wrap_percentage(text, 90).contains("hyphenated-word"));
+}
+
+fn softwrap_text_hyphenated(text: &str) -> String {
+    DocumentFormatter::new_at_prev_checkpoint(
+        text.into(),
+        &TextFormat::new_test_hyphenated(),
+        &TextAnnotations::default(),
+        0,
+    )
+    .0
+    .collect_to_str()
+}
+
+#[test]
+fn hyphen_break_wraps_after_hyphens() {
+    // with `hyphen_break` off a hyphenated compound is treated as a single long word and can
+    // be force-split mid-letter (here inside "word" itself) once it exceeds `max_wrap`.
+    assert_eq!(
+        softwrap_text("xx hyphenated-word\n"),
+        "xx hyphenated-wor\n.d \n "
+    );
+    // with `hyphen_break` on the hyphen becomes a wrap point, so "hyphenated-" stays on the
+    // first line and "word" moves to the next line intact instead of being split mid-letter.
+    assert_eq!(
+        softwrap_text_hyphenated("xx hyphenated-word\n"),
+        "xx hyphenated-\n.word \n "
+    );
+}
+
+fn softwrap_text_preserving_trailing_whitespace(text: &str) -> String {
+    DocumentFormatter::new_at_prev_checkpoint(
+        text.into(),
+        &TextFormat::new_test_preserve_trailing_whitespace(),
+        &TextAnnotations::default(),
+        0,
+    )
+    .0
+    .collect_to_str()
+}
+
+#[test]
+fn preserve_trailing_whitespace_before_wrap() {
+    let text = format!("{}{}bar\n", "x".repeat(13), " ".repeat(5));
+    // by default trailing whitespace landing right at the wrap point is carried onto the
+    // next visual line, ending up misplaced ahead of the wrap indicator and "bar".
+    assert_eq!(softwrap_text(&text), "xxxxxxxxxxxxx    \n. bar \n ");
+    // with `preserve_trailing_whitespace` the whitespace spills past the edge of the first
+    // visual line instead, and "bar" starts the next line unindented.
+    assert_eq!(
+        softwrap_text_preserving_trailing_whitespace(&text),
+        "xxxxxxxxxxxxx     \n.bar \n "
+    );
+}
+
 #[test]
 fn softwrap_indentation() {
     assert_eq!(
@@ -164,6 +310,37 @@ fn annotation() {
         "foo foo foo foo \n.foo foo foo foo \n.foo foo foo  "
     );
 }
+#[test]
+fn annotation_per_grapheme_style() {
+    use crate::doc_formatter::GraphemeSource;
+    use crate::syntax::Highlight;
+
+    let default_highlight = Some(Highlight(0));
+    let type_highlight = Some(Highlight(1));
+    // only the "T" grapheme is overridden; the rest falls back to the layer's highlight
+    let annotation = InlineAnnotation::styled(0, ": T", vec![None, None, type_highlight]);
+
+    let highlights: Vec<_> = DocumentFormatter::new_at_prev_checkpoint(
+        "x".into(),
+        &TextFormat::new_test(false),
+        TextAnnotations::default()
+            .add_inline_annotations(Rc::new([annotation]), default_highlight),
+        0,
+    )
+    .0
+    .take(3)
+    .map(|(grapheme, _)| match grapheme.source {
+        GraphemeSource::VirtualText { highlight } => highlight,
+        GraphemeSource::Document { .. } => panic!("expected virtual text"),
+    })
+    .collect();
+
+    assert_eq!(
+        highlights,
+        vec![default_highlight, default_highlight, type_highlight]
+    );
+}
+
 #[test]
 fn annotation_and_overlay() {
     assert_eq!(
@@ -180,3 +357,143 @@ fn annotation_and_overlay() {
         "fooo  bar "
     );
 }
+
+#[test]
+fn after_anchored_annotation_is_inserted_past_the_grapheme_it_follows() {
+    use crate::text_annotations::AnnotationAnchor;
+
+    // char_idx 1 is "b", the last grapheme on the line - there is no char_idx 2 to anchor
+    // `Before`, but `After` still places the hint right where it belongs.
+    let annotation = InlineAnnotation::new(1, ": T").with_anchor(AnnotationAnchor::After);
+    assert_eq!(
+        DocumentFormatter::new_at_prev_checkpoint(
+            "ab".into(),
+            &TextFormat::new_test(false),
+            TextAnnotations::default().add_inline_annotations(Rc::new([annotation]), None),
+            0,
+        )
+        .0
+        .collect_to_str(),
+        "ab: T "
+    );
+}
+
+#[test]
+fn tab_aligns_to_a_stop_measured_from_the_preceding_annotation() {
+    // tab_width is 2 in `TextFormat::new_test`. A 3-column annotation ("abc") precedes the tab,
+    // so the tab starts at visual column 3 and only needs 1 column to reach the next stop (4) -
+    // not the 2 columns it would need starting from column 0, which is what a tab width computed
+    // from the document column (ignoring the annotation) would wrongly produce.
+    assert_eq!(
+        annotate_text("\t", false, &[InlineAnnotation::new(0, "abc")]),
+        "abc  "
+    );
+}
+
+#[test]
+fn eof_grapheme_is_tagged_distinctly_from_document_text() {
+    let text_fmt = TextFormat::new_test(false);
+    let annotations = TextAnnotations::default();
+    let text: crate::Rope = "ab".into();
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.slice(..), &text_fmt, &annotations, 0);
+
+    let graphemes: Vec<_> = formatter.map(|(grapheme, _)| grapheme).collect();
+    assert_eq!(graphemes.len(), 3); // "a", "b", EOF
+    assert!(!graphemes[0].is_eof());
+    assert!(!graphemes[1].is_eof());
+    assert!(graphemes[2].is_eof());
+    assert_eq!(graphemes[2].doc_chars(), 0);
+}
+
+#[test]
+fn without_eof_grapheme_drops_the_synthetic_trailing_grapheme() {
+    let text_fmt = TextFormat::new_test(false);
+    let annotations = TextAnnotations::default();
+    let text: crate::Rope = "ab".into();
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.slice(..), &text_fmt, &annotations, 0);
+    let graphemes: Vec<_> = formatter.map(|(grapheme, _)| grapheme).collect();
+    assert_eq!(graphemes.len(), 3); // "a", "b", EOF
+
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.slice(..), &text_fmt, &annotations, 0);
+    let graphemes: Vec<_> = formatter
+        .without_eof_grapheme()
+        .map(|(grapheme, _)| grapheme)
+        .collect();
+    assert_eq!(graphemes.len(), 2); // "a", "b" - no EOF
+    assert!(graphemes.iter().all(|g| !g.is_eof()));
+}
+
+#[test]
+fn control_characters_are_substituted_with_their_caret_notation() {
+    // NUL, BEL and DEL - both a low control code and the one control code above the printable
+    // ASCII range - are all substituted with a two-column `^X` representation rather than being
+    // written to the terminal raw.
+    assert_eq!(softwrap_text("a\x00b\x07c\x7fd\n"), "a^@b^Gc^?d \n ");
+}
+
+#[test]
+fn control_character_replacement_has_width_two_and_is_tagged() {
+    let text_fmt = TextFormat::new_test(false);
+    let annotations = TextAnnotations::default();
+    let text: crate::Rope = "\x00\x07\x7f".into();
+    let (formatter, _) =
+        DocumentFormatter::new_at_prev_checkpoint(text.slice(..), &text_fmt, &annotations, 0);
+
+    let graphemes: Vec<_> = formatter
+        .without_eof_grapheme()
+        .map(|(grapheme, _)| grapheme)
+        .collect();
+    assert_eq!(graphemes.len(), 3);
+    for grapheme in &graphemes {
+        assert!(grapheme.grapheme.is_control_char());
+        assert_eq!(grapheme.grapheme.width(), 2);
+    }
+    assert_eq!(graphemes[0].grapheme.to_string(), "^@");
+    assert_eq!(graphemes[1].grapheme.to_string(), "^G");
+    assert_eq!(graphemes[2].grapheme.to_string(), "^?");
+}
+
+#[test]
+fn formatter_cache_reuses_checkpoint_within_a_revision_only() {
+    let text_fmt = TextFormat::new_test(false);
+    let annotations = TextAnnotations::default();
+    let text: crate::Rope = "xx\nyy\nzz\n".into();
+    let mut cache = FormatterCache::new();
+
+    // first lookup in a block populates the cache for that revision
+    let (_, block_start) = DocumentFormatter::new_at_prev_checkpoint_cached(
+        text.slice(..),
+        &text_fmt,
+        &annotations,
+        text.line_to_char(2),
+        &mut cache,
+        0,
+    );
+    assert_eq!(block_start, text.line_to_char(2));
+
+    // a later lookup in the same revision can reuse the checkpoint as a valid (if not
+    // necessarily tightest) block start
+    let (_, block_start) = DocumentFormatter::new_at_prev_checkpoint_cached(
+        text.slice(..),
+        &text_fmt,
+        &annotations,
+        text.line_to_char(2) + 1,
+        &mut cache,
+        0,
+    );
+    assert_eq!(block_start, text.line_to_char(2));
+
+    // a new revision must invalidate the cached checkpoint rather than reuse stale positions
+    let (_, block_start) = DocumentFormatter::new_at_prev_checkpoint_cached(
+        text.slice(..),
+        &text_fmt,
+        &annotations,
+        text.line_to_char(1),
+        &mut cache,
+        1,
+    );
+    assert_eq!(block_start, text.line_to_char(1));
+}
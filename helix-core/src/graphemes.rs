@@ -24,17 +24,30 @@ pub fn tab_width_at(visual_x: usize, tab_width: u16) -> usize {
 pub enum Grapheme<'a> {
     Newline,
     Tab { width: usize },
+    /// A non-printable ASCII control character (`0x00..=0x1F` other than tab/line-endings, or
+    /// `0x7F` DEL), substituted with its two-column caret-notation representation (`^A` for
+    /// `0x01`, `^?` for DEL) so the raw byte is never sent to the terminal but the character can
+    /// still be seen, measured and edited like any other grapheme.
+    ///
+    /// There is no separate variant for invalid UTF-8 or lone surrogates: a `Rope`/`str` can't
+    /// contain either to begin with, so the only "unprintable" input this ever actually sees is a
+    /// genuine ASCII control byte.
+    ControlChar { code: u8 },
     Other { g: GraphemeStr<'a> },
 }
 
 impl<'a> Grapheme<'a> {
     pub fn new(g: GraphemeStr<'a>, visual_x: usize, tab_width: u16) -> Grapheme<'a> {
-        match g {
-            g if g == "\t" => Grapheme::Tab {
+        if g == "\t" {
+            Grapheme::Tab {
                 width: tab_width_at(visual_x, tab_width),
-            },
-            _ if LineEnding::from_str(&g).is_some() => Grapheme::Newline,
-            _ => Grapheme::Other { g },
+            }
+        } else if LineEnding::from_str(&g).is_some() {
+            Grapheme::Newline
+        } else if let Some(code) = ascii_control_code(&g) {
+            Grapheme::ControlChar { code }
+        } else {
+            Grapheme::Other { g }
         }
     }
 
@@ -55,11 +68,16 @@ pub fn width(&self) -> usize {
             Grapheme::Other { ref g } => grapheme_width(g),
             Grapheme::Tab { width } => width,
             Grapheme::Newline => 1,
+            Grapheme::ControlChar { .. } => 2,
         }
     }
 
     pub fn is_whitespace(&self) -> bool {
-        !matches!(&self, Grapheme::Other { g } if !g.chars().all(char_is_whitespace))
+        match self {
+            Grapheme::Other { g } => g.chars().all(char_is_whitespace),
+            Grapheme::ControlChar { .. } => false,
+            Grapheme::Tab { .. } | Grapheme::Newline => true,
+        }
     }
 
     // TODO currently word boundaries are used for softwrapping.
@@ -67,10 +85,31 @@ pub fn is_whitespace(&self) -> bool {
     // This could however be improved in the future by considering unicode
     // character classes but
     pub fn is_word_boundary(&self) -> bool {
-        !matches!(&self, Grapheme::Other { g,.. } if g.chars().all(char_is_word))
+        match self {
+            Grapheme::Other { g } => !g.chars().all(char_is_word),
+            Grapheme::ControlChar { .. } | Grapheme::Tab { .. } | Grapheme::Newline => true,
+        }
+    }
+
+    /// Returns whether this grapheme is a substituted control character - see
+    /// [`Grapheme::ControlChar`].
+    pub fn is_control_char(&self) -> bool {
+        matches!(self, Grapheme::ControlChar { .. })
     }
 }
 
+/// Returns the byte value of `g` if it is a single ASCII control character other than a tab or
+/// line ending (both are matched earlier in [`Grapheme::new`], before this is consulted) - that
+/// is, `0x00..=0x1F` minus `\t`, or `0x7F` (DEL).
+fn ascii_control_code(g: &str) -> Option<u8> {
+    let mut bytes = g.bytes();
+    let byte = bytes.next()?;
+    if bytes.next().is_some() {
+        return None;
+    }
+    (byte < 0x20 || byte == 0x7F).then_some(byte)
+}
+
 impl Display for Grapheme<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -81,6 +120,10 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 }
                 Ok(())
             }
+            Grapheme::ControlChar { code } => {
+                let second = if code == 0x7F { '?' } else { (code + 0x40) as char };
+                write!(f, "^{second}")
+            }
             Grapheme::Other { ref g } => {
                 write!(f, "{g}")
             }
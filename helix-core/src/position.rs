@@ -1,4 +1,4 @@
-use std::{borrow::Cow, cmp::Ordering};
+use std::{borrow::Cow, cmp::Ordering, ops::Range};
 
 use crate::{
     chars::char_is_line_ending,
@@ -202,6 +202,41 @@ pub fn visual_offset_from_anchor(
     Ok((last_pos, block_start))
 }
 
+/// Returns the number of visual rows `range` occupies, including rows produced by soft wrap and
+/// virtual lines from a `LineAnnotation` - the same row accounting `visual_offset_from_anchor`
+/// does, but summed over a whole range instead of resolving a single position. Centralizes what
+/// scrollbar sizing, centering and half-page scroll under soft wrap would otherwise each drive
+/// `DocumentFormatter` by hand to compute. Returns `0` for an empty range.
+pub fn visual_line_count(
+    text: RopeSlice,
+    range: Range<usize>,
+    text_fmt: &TextFormat,
+    annotations: &TextAnnotations,
+) -> usize {
+    if range.is_empty() {
+        return 0;
+    }
+
+    let (formatter, block_start) =
+        DocumentFormatter::new_at_prev_checkpoint(text, text_fmt, annotations, range.start);
+    let mut char_pos = block_start;
+    let mut start_row = None;
+    let mut end_row = 0;
+
+    for (grapheme, vpos) in formatter {
+        if char_pos >= range.end {
+            break;
+        }
+        char_pos += grapheme.doc_chars();
+        if char_pos > range.start {
+            end_row = vpos.row;
+            start_row.get_or_insert(vpos.row);
+        }
+    }
+
+    start_row.map_or(0, |start_row| end_row - start_row + 1)
+}
+
 /// Convert (line, column) coordinates to a character index.
 ///
 /// If the `line` coordinate is beyond the end of the file, the EOF
@@ -400,7 +435,10 @@ pub fn char_idx_at_visual_block_offset(
 
 #[cfg(test)]
 mod test {
+    use std::rc::Rc;
+
     use super::*;
+    use crate::text_annotations::LineAnnotation;
     use crate::Rope;
 
     #[test]
@@ -856,4 +894,61 @@ fn test_char_idx_at_visual_row_offset() {
             0
         );
     }
+
+    #[test]
+    fn test_visual_line_count() {
+        let text = Rope::from("ḧëḷḷö\nẅöṛḷḋ\nfoo");
+        let slice = text.slice(..);
+        let text_fmt = TextFormat::default();
+        let annot = TextAnnotations::default();
+
+        assert_eq!(visual_line_count(slice, 0..0, &text_fmt, &annot), 0);
+        // Within a single line.
+        assert_eq!(visual_line_count(slice, 0..3, &text_fmt, &annot), 1);
+        // Spanning the newline onto the next line.
+        assert_eq!(visual_line_count(slice, 3..8, &text_fmt, &annot), 2);
+        // All three lines.
+        assert_eq!(visual_line_count(slice, 0..slice.len_chars(), &text_fmt, &annot), 3);
+
+        // Soft wrap folds each wrapped row into the count too. With the default
+        // `viewport_width` of 17, "foo ".repeat(10) wraps to 3 visual rows (see
+        // `doc_formatter::test::basic_softwrap`).
+        let mut wrapping_text_fmt = TextFormat::default();
+        wrapping_text_fmt.soft_wrap = true;
+        let wrapped_text = Rope::from("foo ".repeat(10));
+        let wrapped_slice = wrapped_text.slice(..);
+        assert_eq!(
+            visual_line_count(
+                wrapped_slice,
+                0..wrapped_slice.len_chars(),
+                &wrapping_text_fmt,
+                &annot
+            ),
+            3
+        );
+        // Just "foo" is still on the first visual row.
+        assert_eq!(
+            visual_line_count(wrapped_slice, 0..3, &wrapping_text_fmt, &annot),
+            1
+        );
+
+        // A `LineAnnotation` inserts virtual lines below the line it's anchored to, so a range
+        // spanning past that line's newline counts those virtual rows too: without the
+        // annotation, `0..8` above the newline crosses only 2 visual rows.
+        let mut annot_with_virtual_lines = TextAnnotations::default();
+        annot_with_virtual_lines.add_line_annotation(Rc::new([LineAnnotation {
+            anchor_char_idx: 0,
+            height: 2,
+        }]));
+        assert_eq!(
+            visual_line_count(slice, 0..8, &text_fmt, &annot_with_virtual_lines),
+            4
+        );
+        // A range entirely within the line the virtual lines are anchored to isn't affected,
+        // since those rows are only inserted once the line's newline is reached.
+        assert_eq!(
+            visual_line_count(slice, 0..3, &text_fmt, &annot_with_virtual_lines),
+            1
+        );
+    }
 }
@@ -131,6 +131,12 @@ pub struct LanguageConfiguration {
     /// Hardcoded LSP root directories relative to the workspace root, like `examples` or `tools/fuzz`.
     /// Falling back to the current working directory if none are configured.
     pub workspace_lsp_roots: Option<Vec<PathBuf>>,
+
+    /// Minimum length of the word under the cursor before idle completion triggers for this
+    /// language, overriding `Config::completion_trigger_len`. Useful for setting a longer
+    /// threshold on verbose languages (fewer, noisier suggestions) or a shorter one on terse
+    /// languages.
+    pub completion_trigger_len: Option<u8>,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -2145,34 +2151,105 @@ pub enum InjectionLanguageMarker<'a> {
 
 const SHEBANG: &str = r"#!\s*(?:\S*[/\\](?:env\s+(?:\-\S+\s+)*)?)?([^\s\.\d]+)";
 
+/// A single overlay span merged into a highlight event stream by [`merge`].
+///
+/// Plain `(usize, Range<usize>)` tuples (the scope index and the covered char range) still work
+/// everywhere a `Span` is expected, via the `From` impl below - this is additive, not a breaking
+/// change to any of `merge`'s existing callers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub scope: usize,
+    pub range: std::ops::Range<usize>,
+    /// When set, this span suppresses whatever highlights from the underlying stream were open
+    /// over its range instead of layering on top of them - a full-line selection or a conceal
+    /// wants only its own style to show, not the syntax highlight patched underneath it. The
+    /// suppressed highlights are restored once the span ends.
+    ///
+    /// A highlight that starts fresh from the underlying stream *while* a `replace` span is
+    /// active (rather than already being open when the span began) is dropped for the remainder
+    /// of the span rather than tracked for restoration - a token boundary inside a replaced
+    /// region has nothing to usefully restore partway through anyway.
+    pub replace: bool,
+}
+
+impl From<(usize, std::ops::Range<usize>)> for Span {
+    fn from((scope, range): (usize, std::ops::Range<usize>)) -> Self {
+        Span {
+            scope,
+            range,
+            replace: false,
+        }
+    }
+}
+
 pub struct Merge<I> {
     iter: I,
-    spans: Box<dyn Iterator<Item = (usize, std::ops::Range<usize>)>>,
+    spans: Box<dyn Iterator<Item = Span>>,
 
     next_event: Option<HighlightEvent>,
-    next_span: Option<(usize, std::ops::Range<usize>)>,
+    next_span: Option<Span>,
 
     queue: Vec<HighlightEvent>,
+
+    /// Highlights currently open in the emitted stream, tracked so a `replace` span knows what
+    /// it's suppressing and can restore exactly that once it ends.
+    open: Vec<Highlight>,
+    /// Set for the duration of a `replace` span (across however many chunks it gets split into
+    /// by underlying `Source` event boundaries): while set, `HighlightStart`/`HighlightEnd`
+    /// events from `iter` are dropped instead of relayed.
+    in_replace_span: bool,
 }
 
 /// Merge a list of spans into the highlight event stream.
-pub fn merge<I: Iterator<Item = HighlightEvent>>(
+///
+/// Callers chain this: `editor.rs` layers overlay highlights, diagnostics, and focused-view
+/// elements as however many separate `merge` calls are active for the current render (each
+/// optional, depending on what's enabled), boxing the result as `dyn Iterator<Item =
+/// HighlightEvent>` between steps. A fused replacement that emits `(Style, end_char_idx)`
+/// directly - skipping the `HighlightEvent` enum - would only cover a single `merge` step; the
+/// remaining chained layers still need to compose with whatever the previous step produced, so
+/// they would still go through boxed dynamic dispatch over some enum-shaped item to stay generic
+/// over which layers happen to be active. `StyleIter` in `ui::document` is already that one
+/// unavoidable fold from events to styles, sitting at the end of the whole chain rather than
+/// after this single step.
+pub fn merge<I: Iterator<Item = HighlightEvent>, S: Into<Span> + 'static>(
     iter: I,
-    spans: Vec<(usize, std::ops::Range<usize>)>,
+    spans: Vec<S>,
 ) -> Merge<I> {
-    let spans = Box::new(spans.into_iter());
+    let spans = Box::new(spans.into_iter().map(Into::into));
     let mut merge = Merge {
         iter,
         spans,
         next_event: None,
         next_span: None,
         queue: Vec::new(),
+        open: Vec::new(),
+        in_replace_span: false,
     };
     merge.next_event = merge.iter.next();
     merge.next_span = merge.spans.next();
     merge
 }
 
+#[cfg(test)]
+impl<I: Iterator<Item = HighlightEvent>> Merge<I> {
+    /// A snapshot of how far the merge has progressed through `spans`, for tests that want to
+    /// pin down exactly where a regression happens instead of only asserting on the final
+    /// flattened `HighlightEvent` output. There is no separate highlight-overlay type in this tree -
+    /// `Merge` (this struct) is the span-overlay combinator - so this lives here rather than on a
+    /// separate debug-only type.
+    ///
+    /// Returns the span currently being merged in (if any, already clipped to what's left to
+    /// emit of it), and a lower bound on how many further spans remain after it - `spans` is a
+    /// boxed `dyn Iterator`, so an exact count isn't available without consuming it.
+    pub(crate) fn debug_remaining_spans(&self) -> (Option<(usize, std::ops::Range<usize>)>, usize) {
+        (
+            self.next_span.clone().map(|span| (span.scope, span.range)),
+            self.spans.size_hint().0,
+        )
+    }
+}
+
 impl<I: Iterator<Item = HighlightEvent>> Iterator for Merge<I> {
     type Item = HighlightEvent;
     fn next(&mut self) -> Option<Self::Item> {
@@ -2184,11 +2261,14 @@ fn next(&mut self) -> Option<Self::Item> {
         loop {
             match (self.next_event, &self.next_span) {
                 // this happens when range is partially or fully offscreen
-                (Some(Source { start, .. }), Some((span, range))) if start > range.start => {
-                    if start > range.end {
+                (Some(Source { start, .. }), Some(span)) if start > span.range.start => {
+                    if start > span.range.end {
                         self.next_span = self.spans.next();
                     } else {
-                        self.next_span = Some((*span, start..range.end));
+                        self.next_span = Some(Span {
+                            range: start..span.range.end,
+                            ..span.clone()
+                        });
                     };
                 }
                 _ => break,
@@ -2198,14 +2278,24 @@ fn next(&mut self) -> Option<Self::Item> {
         match (self.next_event, &self.next_span) {
             (Some(HighlightStart(i)), _) => {
                 self.next_event = self.iter.next();
+                if self.in_replace_span {
+                    // a `replace` span is covering this highlight: don't let it show, and don't
+                    // bother tracking it either - see `Span::replace`'s doc comment.
+                    return self.next();
+                }
+                self.open.push(i);
                 Some(HighlightStart(i))
             }
             (Some(HighlightEnd), _) => {
                 self.next_event = self.iter.next();
+                if self.in_replace_span {
+                    return self.next();
+                }
+                self.open.pop();
                 Some(HighlightEnd)
             }
-            (Some(Source { start, end }), Some((_, range))) if start < range.start => {
-                let intersect = range.start.min(end);
+            (Some(Source { start, end }), Some(span)) if start < span.range.start => {
+                let intersect = span.range.start.min(end);
                 let event = Source {
                     start,
                     end: intersect,
@@ -2224,16 +2314,12 @@ fn next(&mut self) -> Option<Self::Item> {
 
                 Some(event)
             }
-            (Some(Source { start, end }), Some((span, range))) if start == range.start => {
-                let intersect = range.end.min(end);
-                let event = HighlightStart(Highlight(*span));
-
-                // enqueue in reverse order
-                self.queue.push(HighlightEnd);
-                self.queue.push(Source {
-                    start,
-                    end: intersect,
-                });
+            (Some(Source { start, end }), Some(span)) if start == span.range.start => {
+                let scope = span.scope;
+                let replace = span.replace;
+                let span_end = span.range.end;
+                let intersect = span_end.min(end);
+                let is_final_chunk = intersect == span_end;
 
                 if end == intersect {
                     // the event is complete
@@ -2246,12 +2332,44 @@ fn next(&mut self) -> Option<Self::Item> {
                     });
                 };
 
-                if intersect == range.end {
+                if is_final_chunk {
                     self.next_span = self.spans.next();
                 } else {
-                    self.next_span = Some((*span, intersect..range.end));
+                    self.next_span = Some(Span {
+                        scope,
+                        range: intersect..span_end,
+                        replace,
+                    });
+                }
+
+                // Built up in the order the events should actually be emitted, then drained:
+                // the first is returned directly, the rest are queued in reverse (`queue.pop`
+                // drives every later `next()` call, so pushing back-to-front replays them
+                // forwards).
+                let mut emit = Vec::new();
+                if replace && !self.in_replace_span {
+                    // suppress every highlight currently open before this span's own starts,
+                    // so only the span's style shows for the region it covers.
+                    emit.extend(std::iter::repeat(HighlightEnd).take(self.open.len()));
+                    self.in_replace_span = true;
+                }
+                emit.push(HighlightStart(Highlight(scope)));
+                emit.push(Source {
+                    start,
+                    end: intersect,
+                });
+                emit.push(HighlightEnd);
+                if replace && is_final_chunk {
+                    // restore what was suppressed, outermost first, now that the span is done.
+                    emit.extend(self.open.iter().copied().map(HighlightStart));
+                    self.in_replace_span = false;
                 }
 
+                let mut emit = emit.into_iter();
+                let event = emit.next().expect("always pushes at least HighlightStart");
+                for queued in emit.rev() {
+                    self.queue.push(queued);
+                }
                 Some(event)
             }
             (Some(event), None) => {
@@ -2263,12 +2381,12 @@ fn next(&mut self) -> Option<Self::Item> {
             // even though the range is past the end of the text.  This needs to be
             // handled appropriately by the drawing code by not assuming that
             // all `Source` events point to valid indices in the rope.
-            (None, Some((span, range))) => {
-                let event = HighlightStart(Highlight(*span));
+            (None, Some(span)) => {
+                let event = HighlightStart(Highlight(span.scope));
                 self.queue.push(HighlightEnd);
                 self.queue.push(Source {
-                    start: range.start,
-                    end: range.end,
+                    start: span.range.start,
+                    end: span.range.end,
                 });
                 self.next_span = self.spans.next();
                 Some(event)
@@ -2608,4 +2726,104 @@ fn test_load_runtime_file() {
         let results = load_runtime_file("rust", "does-not-exist");
         assert!(results.is_err());
     }
+
+    // `HighlightEvent` only derives `Debug`, not `PartialEq`, so these helpers destructure it by
+    // hand instead of comparing with `assert_eq!`.
+    fn expect_source(event: Option<HighlightEvent>, start: usize, end: usize) {
+        match event {
+            Some(HighlightEvent::Source { start: s, end: e }) => {
+                assert_eq!((s, e), (start, end))
+            }
+            other => panic!("expected Source {{ {start}..{end} }}, got {other:?}"),
+        }
+    }
+
+    fn expect_highlight_start(event: Option<HighlightEvent>, highlight: usize) {
+        match event {
+            Some(HighlightEvent::HighlightStart(Highlight(h))) => assert_eq!(h, highlight),
+            other => panic!("expected HighlightStart({highlight}), got {other:?}"),
+        }
+    }
+
+    fn expect_highlight_end(event: Option<HighlightEvent>) {
+        assert!(
+            matches!(event, Some(HighlightEvent::HighlightEnd)),
+            "expected HighlightEnd, got {event:?}"
+        );
+    }
+
+    #[test]
+    fn merge_splits_source_events_around_overlaid_spans() {
+        let events = vec![HighlightEvent::Source { start: 0, end: 10 }];
+        let spans = vec![(1usize, 3..6), (2usize, 8..9)];
+        let mut merge = merge(events.into_iter(), spans);
+
+        expect_source(merge.next(), 0, 3);
+        expect_highlight_start(merge.next(), 1);
+        expect_source(merge.next(), 3, 6);
+        expect_highlight_end(merge.next());
+        expect_source(merge.next(), 6, 8);
+        expect_highlight_start(merge.next(), 2);
+        expect_source(merge.next(), 8, 9);
+        expect_highlight_end(merge.next());
+        expect_source(merge.next(), 9, 10);
+        assert!(merge.next().is_none());
+    }
+
+    #[test]
+    fn merge_replace_span_suppresses_and_restores_the_underlying_highlight() {
+        // A syntax highlight (scope 5) spans the whole source; an overlay span with
+        // `replace: true` covers the middle third of it.
+        let events = vec![
+            HighlightEvent::HighlightStart(Highlight(5)),
+            HighlightEvent::Source { start: 0, end: 10 },
+            HighlightEvent::HighlightEnd,
+        ];
+        let spans = vec![Span {
+            scope: 99,
+            range: 3..6,
+            replace: true,
+        }];
+        let mut merge = merge(events.into_iter(), spans);
+
+        expect_highlight_start(merge.next(), 5);
+        expect_source(merge.next(), 0, 3);
+        // scope 5 is suppressed for the replaced region...
+        expect_highlight_end(merge.next());
+        expect_highlight_start(merge.next(), 99);
+        expect_source(merge.next(), 3, 6);
+        expect_highlight_end(merge.next());
+        // ...and restored once the replace span ends.
+        expect_highlight_start(merge.next(), 5);
+        expect_source(merge.next(), 6, 10);
+        expect_highlight_end(merge.next());
+        assert!(merge.next().is_none());
+    }
+
+    #[test]
+    fn merge_debug_remaining_spans_tracks_progress_through_the_merge() {
+        let events = vec![HighlightEvent::Source { start: 0, end: 10 }];
+        let spans = vec![(1usize, 3..6), (2usize, 8..9)];
+        let mut merge = merge(events.into_iter(), spans);
+
+        // Both spans are already queued up before any event is pulled: the first is the
+        // in-progress `next_span`, the second is still behind it in `spans`.
+        assert_eq!(merge.debug_remaining_spans(), (Some((1, 3..6)), 1));
+
+        expect_source(merge.next(), 0, 3);
+        // Unconsumed source before the first span doesn't touch `next_span` yet.
+        assert_eq!(merge.debug_remaining_spans(), (Some((1, 3..6)), 1));
+
+        expect_highlight_start(merge.next(), 1);
+        expect_source(merge.next(), 3, 6);
+        expect_highlight_end(merge.next());
+        // The first span has been fully emitted; the second is now current and nothing is left
+        // behind it.
+        assert_eq!(merge.debug_remaining_spans(), (Some((2, 8..9)), 0));
+
+        for _ in 0..5 {
+            merge.next();
+        }
+        assert_eq!(merge.debug_remaining_spans(), (None, 0));
+    }
 }
@@ -5,13 +5,31 @@
 use crate::syntax::Highlight;
 use crate::Tendril;
 
-/// An inline annotation is continuous text shown
-/// on the screen before the grapheme that starts at
-/// `char_idx`
+/// Where an [`InlineAnnotation`] is inserted relative to the grapheme at its `char_idx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnnotationAnchor {
+    /// Insert before the grapheme that starts at `char_idx` - the only behavior available before
+    /// this enum existed, and still what most annotations (inlay hints, padding) want.
+    #[default]
+    Before,
+    /// Insert after the grapheme that starts at `char_idx`, once it has been fully consumed.
+    /// Prefer this over anchoring a `Before` annotation at `char_idx + 1`: that offset lands
+    /// inside the next grapheme cluster when it's a combining mark, and doesn't exist at all when
+    /// `char_idx` is the last character on the line.
+    After,
+}
+
+/// An inline annotation is continuous text shown on the screen next to the grapheme that starts
+/// at `char_idx`, on the side given by `anchor`.
 #[derive(Debug, Clone)]
 pub struct InlineAnnotation {
     pub text: Tendril,
     pub char_idx: usize,
+    pub anchor: AnnotationAnchor,
+    /// Per-grapheme highlight overrides for `text`, indexed by grapheme position.
+    /// A missing entry (or `None` at an index) falls back to the layer's highlight
+    /// passed to [`TextAnnotations::add_inline_annotations`].
+    pub styles: Option<Rc<[Option<Highlight>]>>,
 }
 
 impl InlineAnnotation {
@@ -19,8 +37,34 @@ pub fn new(char_idx: usize, text: impl Into<Tendril>) -> Self {
         Self {
             char_idx,
             text: text.into(),
+            anchor: AnnotationAnchor::Before,
+            styles: None,
         }
     }
+
+    /// Like [`Self::new`] but styles individual graphemes of `text` differently, for example a
+    /// type hint where the type name and the surrounding punctuation use different highlights.
+    /// `styles` is indexed by grapheme position; an index with `None` (or past the end of
+    /// `styles`) falls back to the layer's highlight.
+    pub fn styled(
+        char_idx: usize,
+        text: impl Into<Tendril>,
+        styles: impl Into<Rc<[Option<Highlight>]>>,
+    ) -> Self {
+        Self {
+            char_idx,
+            text: text.into(),
+            anchor: AnnotationAnchor::Before,
+            styles: Some(styles.into()),
+        }
+    }
+
+    /// Anchors this annotation after the grapheme at `char_idx` instead of before it; see
+    /// [`AnnotationAnchor::After`].
+    pub fn with_anchor(mut self, anchor: AnnotationAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
 }
 
 /// Represents a **single Grapheme** that is part of the document
@@ -91,10 +135,64 @@ pub struct LineAnnotation {
     pub height: usize,
 }
 
+/// A `BlockAnnotation` behaves like a [`LineAnnotation`] that reserves `lines.len()` blank
+/// virtual lines below the document line containing `anchor_char_idx`, but also carries the
+/// text to fill them. This avoids the boilerplate of keeping a `LineAnnotation`'s `height` in
+/// sync with a separate `LineDecoration` for simple cases, such as a one-line inline diagnostic
+/// shown below a line, at the cost of the flexibility a fully custom decoration offers.
+#[derive(Debug, Clone)]
+pub struct BlockAnnotation {
+    pub anchor_char_idx: usize,
+    pub lines: Rc<[(String, Option<Highlight>)]>,
+}
+
+/// Anchors a background `highlight` to the entire document line starting at `anchor_char_idx` -
+/// every visual row it occupies, including any produced by soft wrap - for features like
+/// highlighting the line under a diagnostic or a diff hunk. Unlike [`LineAnnotation`], this
+/// doesn't reserve any extra rows; it colors the rows the line already has, so it needs no
+/// special-casing for soft wrap: the renderer just re-applies it to every visual row belonging to
+/// `anchor_char_idx`'s line, exactly as it would to a single-line one. `anchor_char_idx` has to
+/// be the line's first char (the index `Rope::line_to_char` returns); an anchor anywhere else in
+/// the line matches no row and highlights nothing.
+#[derive(Debug, Clone, Copy)]
+pub struct LineHighlight {
+    pub anchor_char_idx: usize,
+    pub highlight: Highlight,
+}
+
+/// `reset_pos` falls back to a full binary search once the requested position has moved
+/// further ahead of the last reset position than this, since at that point a linear scan
+/// from the old index costs more than just searching the whole layer.
+const RESET_LINEAR_SCAN_THRESHOLD: usize = 32;
+
+/// Priority used by [`TextAnnotations::add_overlay`] and [`TextAnnotations::add_overlay_named`].
+pub const DEFAULT_OVERLAY_PRIORITY: i32 = 0;
+
+/// Identifies a named annotation layer so its data can be replaced, or the layer removed,
+/// without rebuilding or disturbing any other layer's iteration cursor. Callers choose their
+/// own id (for example `"diagnostics"` or `"inline-hints"`) rather than receiving a generated
+/// handle back from `TextAnnotations`.
+pub type AnnotationLayerId = &'static str;
+
 #[derive(Debug)]
 struct Layer<A, M> {
     annotations: Rc<[A]>,
     current_index: Cell<usize>,
+    /// The `char_idx` most recently passed to `reset_pos`, used to skip the search entirely
+    /// when rendering repeatedly resets to the same position, and to choose a cheap linear
+    /// scan over a full binary search when it only moved a little.
+    last_reset_pos: Cell<Option<usize>>,
+    /// Set whenever [`Self::consume_if`] (or `TextAnnotations::annotation_lines_at`, for line
+    /// and block annotation layers) advances `current_index`, and cleared by
+    /// [`Self::reset_pos`]. Guards the `last_reset_pos == char_idx` fast path: without it, a
+    /// `reset_pos` call that repeats the previous position would wrongly no-op even though the
+    /// cursor has since moved past that position, e.g. from a lookahead query at a later
+    /// `char_idx`.
+    consumed_since_reset: Cell<bool>,
+    /// Set by the `*_named` constructors so the layer can later be found again by
+    /// [`replace_layer`] or [`remove_layer`]. Anonymous layers (the common case) leave this
+    /// `None` and can only be cleared wholesale, like [`TextAnnotations::clear_line_annotations`].
+    id: Option<AnnotationLayerId>,
     metadata: M,
 }
 
@@ -103,6 +201,9 @@ fn clone(&self) -> Self {
         Layer {
             annotations: self.annotations.clone(),
             current_index: self.current_index.clone(),
+            last_reset_pos: self.last_reset_pos.clone(),
+            consumed_since_reset: self.consumed_since_reset.clone(),
+            id: self.id,
             metadata: self.metadata.clone(),
         }
     }
@@ -110,17 +211,56 @@ fn clone(&self) -> Self {
 
 impl<A, M> Layer<A, M> {
     pub fn reset_pos(&self, char_idx: usize, get_char_idx: impl Fn(&A) -> usize) {
-        let new_index = self
-            .annotations
-            .partition_point(|annot| get_char_idx(annot) < char_idx);
+        if self.last_reset_pos.get() == Some(char_idx) && !self.consumed_since_reset.get() {
+            return;
+        }
+        let old_pos = self.last_reset_pos.replace(Some(char_idx));
+        let old_index = self.current_index.get();
+        // `old_index` only lines up with `old_pos` if nothing was consumed since the layer was
+        // last reset - a lookahead query at a later `char_idx` (without an intervening
+        // `reset_pos`) leaves it pointing past where `old_pos` would place it, so the linear
+        // scan forward from `old_index` can no longer be trusted and a full search is needed.
+        let can_scan_forward = !self.consumed_since_reset.replace(false);
+
+        let new_index = match old_pos {
+            Some(old_pos) if can_scan_forward && old_pos <= char_idx => {
+                let mut index = old_index;
+                while index < self.annotations.len() && index - old_index < RESET_LINEAR_SCAN_THRESHOLD
+                {
+                    if get_char_idx(&self.annotations[index]) >= char_idx {
+                        break;
+                    }
+                    index += 1;
+                }
+                index + self.annotations[index..].partition_point(|annot| get_char_idx(annot) < char_idx)
+            }
+            _ => self
+                .annotations
+                .partition_point(|annot| get_char_idx(annot) < char_idx),
+        };
         self.current_index.set(new_index);
     }
 
     pub fn consume(&self, char_idx: usize, get_char_idx: impl Fn(&A) -> usize) -> Option<&A> {
+        self.consume_if(char_idx, get_char_idx, |_| true)
+    }
+
+    /// Like [`Self::consume`] but only pops the annotation at `char_idx` if `predicate` also
+    /// accepts it, leaving it in place (and the cursor un-advanced) otherwise. Used to let
+    /// [`TextAnnotations::next_inline_annotation_at`] and
+    /// [`TextAnnotations::next_after_inline_annotation_at`] each drain their own
+    /// [`AnnotationAnchor`] out of a single, jointly-sorted layer.
+    fn consume_if(
+        &self,
+        char_idx: usize,
+        get_char_idx: impl Fn(&A) -> usize,
+        predicate: impl Fn(&A) -> bool,
+    ) -> Option<&A> {
         let annot = self.annotations.get(self.current_index.get())?;
         debug_assert!(get_char_idx(annot) >= char_idx);
-        if get_char_idx(annot) == char_idx {
+        if get_char_idx(annot) == char_idx && predicate(annot) {
             self.current_index.set(self.current_index.get() + 1);
+            self.consumed_since_reset.set(true);
             Some(annot)
         } else {
             None
@@ -133,6 +273,9 @@ fn from((annotations, metadata): (Rc<[A]>, M)) -> Layer<A, M> {
         Layer {
             annotations,
             current_index: Cell::new(0),
+            last_reset_pos: Cell::new(None),
+            consumed_since_reset: Cell::new(false),
+            id: None,
             metadata,
         }
     }
@@ -144,13 +287,36 @@ fn reset_pos<A, M>(layers: &[Layer<A, M>], pos: usize, get_pos: impl Fn(&A) -> u
     }
 }
 
+/// Swaps the data of the layer named `id` in place, resetting its cursor so the next
+/// `reset_pos` re-derives it from the new data. Returns `false` if no layer in `layers` was
+/// added with that id, leaving `layers` untouched.
+fn replace_layer<A, M>(layers: &mut [Layer<A, M>], id: AnnotationLayerId, new_data: Rc<[A]>) -> bool {
+    match layers.iter_mut().find(|layer| layer.id == Some(id)) {
+        Some(layer) => {
+            layer.annotations = new_data;
+            layer.current_index.set(0);
+            layer.last_reset_pos.set(None);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes the layer named `id` from `layers`, if present.
+fn remove_layer<A, M>(layers: &mut Vec<Layer<A, M>>, id: AnnotationLayerId) {
+    layers.retain(|layer| layer.id != Some(id));
+}
+
 /// Annotations that change that is displayed when the document is render.
 /// Also commonly called virtual text.
 #[derive(Default, Debug, Clone)]
 pub struct TextAnnotations {
     inline_annotations: Vec<Layer<InlineAnnotation, Option<Highlight>>>,
-    overlays: Vec<Layer<Overlay, Option<Highlight>>>,
+    /// Metadata is `(highlight, priority)`; see [`TextAnnotations::add_overlay_with_priority`].
+    overlays: Vec<Layer<Overlay, (Option<Highlight>, i32)>>,
     line_annotations: Vec<Layer<LineAnnotation, ()>>,
+    block_annotations: Vec<Layer<BlockAnnotation, ()>>,
+    line_highlights: Vec<Layer<LineHighlight, ()>>,
 }
 
 impl TextAnnotations {
@@ -161,6 +327,9 @@ pub fn reset_pos(&self, char_idx: usize) {
         reset_pos(&self.line_annotations, char_idx, |annot| {
             annot.anchor_char_idx
         });
+        reset_pos(&self.block_annotations, char_idx, |annot| {
+            annot.anchor_char_idx
+        });
     }
 
     pub fn collect_overlay_highlights(
@@ -178,7 +347,7 @@ pub fn collect_overlay_highlights(
             }
         }
 
-        highlights
+        merge_adjacent_spans(highlights)
     }
 
     /// Add new inline annotations.
@@ -189,6 +358,10 @@ pub fn collect_overlay_highlights(
     /// The annotations **must be sorted** by their `char_idx`.
     /// Multiple annotations with the same `char_idx` are allowed,
     /// they will be display in the order that they are present in the layer.
+    /// If a `char_idx` mixes `Before` and `After`-anchored annotations (see
+    /// [`AnnotationAnchor`]), all of the `Before` ones must come first: `Before` annotations at a
+    /// position are drained before the document grapheme there is even considered, so an `After`
+    /// annotation sorted ahead of one would starve it.
     ///
     /// If multiple layers contain annotations at the same position
     /// the annotations that belong to the layers added first will be shown first.
@@ -201,6 +374,36 @@ pub fn add_inline_annotations(
         self
     }
 
+    /// Like [`Self::add_inline_annotations`] but records `id` so the layer can later be
+    /// updated with [`Self::replace_inline_annotations_layer`] or removed with
+    /// [`Self::remove_inline_annotations_layer`] without rebuilding any other layer.
+    pub fn add_inline_annotations_named(
+        &mut self,
+        id: AnnotationLayerId,
+        layer: Rc<[InlineAnnotation]>,
+        highlight: Option<Highlight>,
+    ) -> &mut Self {
+        let mut layer: Layer<_, _> = (layer, highlight).into();
+        layer.id = Some(id);
+        self.inline_annotations.push(layer);
+        self
+    }
+
+    /// Replaces the data of the inline annotation layer named `id`, added via
+    /// [`Self::add_inline_annotations_named`]. Returns `false` if no such layer exists.
+    pub fn replace_inline_annotations_layer(
+        &mut self,
+        id: AnnotationLayerId,
+        new_data: Rc<[InlineAnnotation]>,
+    ) -> bool {
+        replace_layer(&mut self.inline_annotations, id, new_data)
+    }
+
+    /// Removes the inline annotation layer named `id`, if present.
+    pub fn remove_inline_annotations_layer(&mut self, id: AnnotationLayerId) {
+        remove_layer(&mut self.inline_annotations, id);
+    }
+
     /// Add new grapheme overlays.
     ///
     /// The overlaid grapheme will be rendered with `highlight`
@@ -209,13 +412,55 @@ pub fn add_inline_annotations(
     /// The overlays **must be sorted** by their `char_idx`.
     /// Multiple overlays with the same `char_idx` **are allowed**.
     ///
-    /// If multiple layers contain overlay at the same position
-    /// the overlay from the layer added last will be show.
+    /// Uses [`DEFAULT_OVERLAY_PRIORITY`]; among overlays at the same priority for a given
+    /// position, the one from the layer added last wins. Use [`Self::add_overlay_with_priority`]
+    /// to opt out of add-order-dependent behavior, e.g. to make a search-match overlay always win
+    /// over a whitespace-rendering one regardless of which was added first.
     pub fn add_overlay(&mut self, layer: Rc<[Overlay]>, highlight: Option<Highlight>) -> &mut Self {
-        self.overlays.push((layer, highlight).into());
+        self.add_overlay_with_priority(layer, highlight, DEFAULT_OVERLAY_PRIORITY)
+    }
+
+    /// Like [`Self::add_overlay`] but `priority` decides which overlay wins when multiple layers
+    /// have one at the same position: the highest priority wins outright, and among equal
+    /// priorities the layer added last wins, same as [`Self::add_overlay`].
+    pub fn add_overlay_with_priority(
+        &mut self,
+        layer: Rc<[Overlay]>,
+        highlight: Option<Highlight>,
+        priority: i32,
+    ) -> &mut Self {
+        self.overlays.push((layer, (highlight, priority)).into());
         self
     }
 
+    /// Like [`Self::add_overlay`] but records `id` so the layer's data can later be swapped
+    /// in place with [`Self::replace_overlay_layer`], or the layer removed with
+    /// [`Self::remove_overlay_layer`], without touching any other overlay layer. Useful for a
+    /// layer that is rebuilt independently of the others, such as a diagnostics overlay that
+    /// is recomputed far more often than an inline-hints overlay.
+    pub fn add_overlay_named(
+        &mut self,
+        id: AnnotationLayerId,
+        layer: Rc<[Overlay]>,
+        highlight: Option<Highlight>,
+    ) -> &mut Self {
+        let mut layer: Layer<_, _> = (layer, (highlight, DEFAULT_OVERLAY_PRIORITY)).into();
+        layer.id = Some(id);
+        self.overlays.push(layer);
+        self
+    }
+
+    /// Replaces the data of the overlay layer named `id`, added via
+    /// [`Self::add_overlay_named`]. Returns `false` if no such layer exists.
+    pub fn replace_overlay_layer(&mut self, id: AnnotationLayerId, new_data: Rc<[Overlay]>) -> bool {
+        replace_layer(&mut self.overlays, id, new_data)
+    }
+
+    /// Removes the overlay layer named `id`, if present.
+    pub fn remove_overlay_layer(&mut self, id: AnnotationLayerId) {
+        remove_layer(&mut self.overlays, id);
+    }
+
     /// Add new annotation lines.
     ///
     /// The line annotations **must be sorted** by their `char_idx`.
@@ -225,40 +470,197 @@ pub fn add_line_annotation(&mut self, layer: Rc<[LineAnnotation]>) -> &mut Self
         self
     }
 
+    /// Like [`Self::add_line_annotation`] but records `id` so the layer can later be updated
+    /// with [`Self::replace_line_annotations_layer`] or removed with
+    /// [`Self::remove_line_annotations_layer`] without touching any other line annotation layer.
+    pub fn add_line_annotation_named(
+        &mut self,
+        id: AnnotationLayerId,
+        layer: Rc<[LineAnnotation]>,
+    ) -> &mut Self {
+        let mut layer: Layer<_, _> = (layer, ()).into();
+        layer.id = Some(id);
+        self.line_annotations.push(layer);
+        self
+    }
+
+    /// Replaces the data of the line annotation layer named `id`, added via
+    /// [`Self::add_line_annotation_named`]. Returns `false` if no such layer exists.
+    pub fn replace_line_annotations_layer(
+        &mut self,
+        id: AnnotationLayerId,
+        new_data: Rc<[LineAnnotation]>,
+    ) -> bool {
+        replace_layer(&mut self.line_annotations, id, new_data)
+    }
+
+    /// Removes the line annotation layer named `id`, if present.
+    pub fn remove_line_annotations_layer(&mut self, id: AnnotationLayerId) {
+        remove_layer(&mut self.line_annotations, id);
+    }
+
+    /// Add new block annotations.
+    ///
+    /// Like [`Self::add_line_annotation`] this reserves `lines.len()` blank virtual lines
+    /// below the document line containing `anchor_char_idx`, but the text to fill them is
+    /// taken from `lines` itself rather than a separately coordinated `LineDecoration`.
+    ///
+    /// The block annotations **must be sorted** by their `anchor_char_idx`.
+    /// Multiple block annotations with the same `anchor_char_idx` **are not allowed**.
+    pub fn add_block_annotations(&mut self, layer: Rc<[BlockAnnotation]>) -> &mut Self {
+        self.block_annotations.push((layer, ()).into());
+        self
+    }
+
+    /// Like [`Self::add_block_annotations`] but records `id` so the layer can later be updated
+    /// with [`Self::replace_block_annotations_layer`] or removed with
+    /// [`Self::remove_block_annotations_layer`] without touching any other block annotation layer.
+    pub fn add_block_annotations_named(
+        &mut self,
+        id: AnnotationLayerId,
+        layer: Rc<[BlockAnnotation]>,
+    ) -> &mut Self {
+        let mut layer: Layer<_, _> = (layer, ()).into();
+        layer.id = Some(id);
+        self.block_annotations.push(layer);
+        self
+    }
+
+    /// Replaces the data of the block annotation layer named `id`, added via
+    /// [`Self::add_block_annotations_named`]. Returns `false` if no such layer exists.
+    pub fn replace_block_annotations_layer(
+        &mut self,
+        id: AnnotationLayerId,
+        new_data: Rc<[BlockAnnotation]>,
+    ) -> bool {
+        replace_layer(&mut self.block_annotations, id, new_data)
+    }
+
+    /// Removes the block annotation layer named `id`, if present.
+    pub fn remove_block_annotations_layer(&mut self, id: AnnotationLayerId) {
+        remove_layer(&mut self.block_annotations, id);
+    }
+
+    /// Add new line highlights.
+    ///
+    /// The line highlights **must be sorted** by their `anchor_char_idx`.
+    /// Multiple line highlights with the same `anchor_char_idx` **are not allowed**.
+    pub fn add_line_highlight(&mut self, layer: Rc<[LineHighlight]>) -> &mut Self {
+        self.line_highlights.push((layer, ()).into());
+        self
+    }
+
+    /// Like [`Self::add_line_highlight`] but records `id` so the layer can later be updated
+    /// with [`Self::replace_line_highlights_layer`] or removed with
+    /// [`Self::remove_line_highlights_layer`] without touching any other line highlight layer.
+    pub fn add_line_highlight_named(
+        &mut self,
+        id: AnnotationLayerId,
+        layer: Rc<[LineHighlight]>,
+    ) -> &mut Self {
+        let mut layer: Layer<_, _> = (layer, ()).into();
+        layer.id = Some(id);
+        self.line_highlights.push(layer);
+        self
+    }
+
+    /// Replaces the data of the line highlight layer named `id`, added via
+    /// [`Self::add_line_highlight_named`]. Returns `false` if no such layer exists.
+    pub fn replace_line_highlights_layer(
+        &mut self,
+        id: AnnotationLayerId,
+        new_data: Rc<[LineHighlight]>,
+    ) -> bool {
+        replace_layer(&mut self.line_highlights, id, new_data)
+    }
+
+    /// Removes the line highlight layer named `id`, if present.
+    pub fn remove_line_highlights_layer(&mut self, id: AnnotationLayerId) {
+        remove_layer(&mut self.line_highlights, id);
+    }
+
+    /// Returns the background [`Highlight`] whose anchor is exactly `char_idx`, if any; callers
+    /// pass a line's first char. An anchor elsewhere in that line is not matched. Like [`Self::block_annotation_at`], this does a direct scan of each layer rather
+    /// than advancing a cursor, so - unlike `annotation_lines_at` - it can be called repeatedly
+    /// with the *same* `char_idx` (once per visual row of a soft-wrapped line) instead of only
+    /// once. Layers are checked in the order they were added; the first match wins.
+    pub fn line_highlight_at(&self, char_idx: usize) -> Option<Highlight> {
+        self.line_highlights.iter().find_map(|layer| {
+            layer
+                .annotations
+                .iter()
+                .find(|annot| annot.anchor_char_idx == char_idx)
+                .map(|annot| annot.highlight)
+        })
+    }
+
     /// Removes all line annotations, useful for vertical motions
     /// so that virtual text lines are automatically skipped.
     pub fn clear_line_annotations(&mut self) {
         self.line_annotations.clear();
     }
 
+    /// Returns the next [`AnnotationAnchor::Before`] annotation due at `char_idx`, i.e. one that
+    /// should be inserted before the document grapheme starting there.
     pub(crate) fn next_inline_annotation_at(
         &self,
         char_idx: usize,
     ) -> Option<(&InlineAnnotation, Option<Highlight>)> {
         self.inline_annotations.iter().find_map(|layer| {
-            let annotation = layer.consume(char_idx, |annot| annot.char_idx)?;
+            let annotation = layer.consume_if(
+                char_idx,
+                |annot| annot.char_idx,
+                |annot| annot.anchor == AnnotationAnchor::Before,
+            )?;
+            Some((annotation, layer.metadata))
+        })
+    }
+
+    /// Returns the next [`AnnotationAnchor::After`] annotation anchored at `char_idx`, i.e. one
+    /// that should be inserted once the document grapheme starting there has been fully consumed.
+    /// Unlike [`Self::next_inline_annotation_at`], `char_idx` here is a position the formatter has
+    /// already moved past, not its current one.
+    pub(crate) fn next_after_inline_annotation_at(
+        &self,
+        char_idx: usize,
+    ) -> Option<(&InlineAnnotation, Option<Highlight>)> {
+        self.inline_annotations.iter().find_map(|layer| {
+            let annotation = layer.consume_if(
+                char_idx,
+                |annot| annot.char_idx,
+                |annot| annot.anchor == AnnotationAnchor::After,
+            )?;
             Some((annotation, layer.metadata))
         })
     }
 
+    /// Returns the highest-priority overlay for `char_idx`. Ties (including the default
+    /// priority every layer gets unless [`Self::add_overlay_with_priority`] is used) go to the
+    /// layer that was added last, matching the historical "last layer wins" behavior.
     pub(crate) fn overlay_at(&self, char_idx: usize) -> Option<(&Overlay, Option<Highlight>)> {
-        let mut overlay = None;
+        let mut overlay: Option<(&Overlay, (Option<Highlight>, i32))> = None;
         for layer in &self.overlays {
             while let Some(new_overlay) = layer.consume(char_idx, |annot| annot.char_idx) {
-                overlay = Some((new_overlay, layer.metadata));
+                let is_higher_or_equal_priority = overlay
+                    .map_or(true, |(_, (_, priority))| layer.metadata.1 >= priority);
+                if is_higher_or_equal_priority {
+                    overlay = Some((new_overlay, layer.metadata));
+                }
             }
         }
-        overlay
+        overlay.map(|(overlay, (highlight, _))| (overlay, highlight))
     }
 
     pub(crate) fn annotation_lines_at(&self, char_idx: usize) -> usize {
-        self.line_annotations
+        let line_annotation_lines: usize = self
+            .line_annotations
             .iter()
             .map(|layer| {
                 let mut lines = 0;
                 while let Some(annot) = layer.annotations.get(layer.current_index.get()) {
                     if annot.anchor_char_idx == char_idx {
                         layer.current_index.set(layer.current_index.get() + 1);
+                        layer.consumed_since_reset.set(true);
                         lines += annot.height
                     } else {
                         break;
@@ -266,6 +668,169 @@ pub(crate) fn annotation_lines_at(&self, char_idx: usize) -> usize {
                 }
                 lines
             })
-            .sum()
+            .sum();
+        let block_annotation_lines: usize = self
+            .block_annotations
+            .iter()
+            .map(|layer| {
+                let mut lines = 0;
+                while let Some(annot) = layer.annotations.get(layer.current_index.get()) {
+                    if annot.anchor_char_idx == char_idx {
+                        layer.current_index.set(layer.current_index.get() + 1);
+                        layer.consumed_since_reset.set(true);
+                        lines += annot.lines.len()
+                    } else {
+                        break;
+                    }
+                }
+                lines
+            })
+            .sum();
+        line_annotation_lines + block_annotation_lines
+    }
+
+    /// Returns the [`BlockAnnotation`] anchored at `char_idx`, if any, for use by the
+    /// built-in decoration that renders its `lines` into the virtual lines reserved by
+    /// `annotation_lines_at`. Unlike `annotation_lines_at` this does not advance any
+    /// layer's cursor, so it can be queried independently of the formatting pass.
+    pub fn block_annotation_at(&self, char_idx: usize) -> Option<&BlockAnnotation> {
+        self.block_annotations.iter().find_map(|layer| {
+            layer
+                .annotations
+                .iter()
+                .find(|annot| annot.anchor_char_idx == char_idx)
+        })
+    }
+}
+
+/// Coalesces consecutive spans that share the same scope and whose ranges touch or overlap
+/// into a single span. The input is expected to already be sorted by range start (as produced
+/// by [`TextAnnotations::collect_overlay_highlights`]); the result preserves that order and
+/// remains non-overlapping, satisfying the invariant `overlay` relies on, while emitting far
+/// fewer `HighlightStart`/`HighlightEnd` events for runs of identically-scoped single-char spans.
+fn merge_adjacent_spans(spans: Vec<(usize, Range<usize>)>) -> Vec<(usize, Range<usize>)> {
+    let mut merged: Vec<(usize, Range<usize>)> = Vec::with_capacity(spans.len());
+    for (scope, range) in spans {
+        if let Some((last_scope, last_range)) = merged.last_mut() {
+            if *last_scope == scope && range.start <= last_range.end {
+                last_range.end = last_range.end.max(range.end);
+                continue;
+            }
+        }
+        merged.push((scope, range));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn merge_adjacent_spans_coalesces_same_scope_runs() {
+        let spans: Vec<_> = (0..10).map(|i| (5, i..i + 1)).collect();
+        let merged = merge_adjacent_spans(spans);
+        assert_eq!(merged, vec![(5, 0..10)]);
+    }
+
+    #[test]
+    fn reset_pos_short_circuits_and_scans_forward() {
+        let layer: Layer<InlineAnnotation, ()> = (
+            Rc::from((0..100).map(|i| InlineAnnotation::new(i * 2, "x")).collect::<Vec<_>>()),
+            (),
+        )
+            .into();
+        let get_char_idx = |annot: &InlineAnnotation| annot.char_idx;
+
+        layer.reset_pos(10, get_char_idx);
+        assert_eq!(layer.current_index.get(), 5);
+
+        // setting `current_index` directly (not through `consume_if`) leaves
+        // `consumed_since_reset` clear, so a repeat call at the same position takes the fast
+        // path and leaves the cursor alone
+        layer.current_index.set(999);
+        layer.reset_pos(10, get_char_idx);
+        assert_eq!(layer.current_index.get(), 999);
+
+        // a small forward move should land on the same index a full binary search would find
+        layer.last_reset_pos.set(Some(10));
+        layer.current_index.set(5);
+        layer.reset_pos(20, get_char_idx);
+        assert_eq!(layer.current_index.get(), 10);
+
+        // a move backwards (e.g. scrolling up) must fall back to a full search rather than
+        // scanning forward from a now-invalid index
+        layer.reset_pos(0, get_char_idx);
+        assert_eq!(layer.current_index.get(), 0);
+    }
+
+    #[test]
+    fn named_overlay_layer_can_be_replaced_and_removed() {
+        let mut annotations = TextAnnotations::default();
+        annotations.add_overlay_named("diagnostics", Rc::new([Overlay::new(0, "X")]), None);
+        annotations.add_overlay(Rc::new([Overlay::new(1, "Y")]), None);
+
+        annotations.reset_pos(0);
+        assert_eq!(annotations.overlay_at(0).unwrap().0.grapheme, Tendril::from("X"));
+
+        // replacing the named layer must not disturb the anonymous layer added alongside it
+        assert!(annotations.replace_overlay_layer("diagnostics", Rc::new([Overlay::new(0, "Z")])));
+        annotations.reset_pos(0);
+        assert_eq!(annotations.overlay_at(0).unwrap().0.grapheme, Tendril::from("Z"));
+        assert_eq!(annotations.overlay_at(1).unwrap().0.grapheme, Tendril::from("Y"));
+
+        // replacing (or removing) an id that was never added is a harmless no-op
+        assert!(!annotations.replace_overlay_layer("inline-hints", Rc::new([])));
+
+        annotations.remove_overlay_layer("diagnostics");
+        annotations.reset_pos(0);
+        assert!(annotations.overlay_at(0).is_none());
+        assert_eq!(annotations.overlay_at(1).unwrap().0.grapheme, Tendril::from("Y"));
+    }
+
+    #[test]
+    fn higher_priority_overlay_wins_regardless_of_add_order() {
+        let mut annotations = TextAnnotations::default();
+        // added first, but at a higher priority than the layer added after it - it must still
+        // win, unlike the old "last layer wins" rule which would have picked "W" below
+        annotations.add_overlay_with_priority(Rc::new([Overlay::new(0, "S")]), None, 10);
+        annotations.add_overlay(Rc::new([Overlay::new(0, "W")]), None);
+
+        annotations.reset_pos(0);
+        assert_eq!(annotations.overlay_at(0).unwrap().0.grapheme, Tendril::from("S"));
+    }
+
+    #[test]
+    fn block_annotation_reserves_and_exposes_its_lines() {
+        let mut annotations = TextAnnotations::default();
+        annotations.add_block_annotations(Rc::new([BlockAnnotation {
+            anchor_char_idx: 5,
+            lines: Rc::new([("error: oops".to_string(), None)]),
+        }]));
+
+        annotations.reset_pos(0);
+        assert_eq!(annotations.annotation_lines_at(0), 0);
+        assert_eq!(annotations.annotation_lines_at(5), 1);
+
+        // the accessor used by the renderer must not depend on `annotation_lines_at` having
+        // been called first, since the two are queried during separate passes
+        annotations.reset_pos(0);
+        let block = annotations.block_annotation_at(5).unwrap();
+        assert_eq!(block.lines[0].0, "error: oops");
+    }
+
+    #[test]
+    fn line_highlight_at_can_be_queried_repeatedly_for_the_same_anchor() {
+        let mut annotations = TextAnnotations::default();
+        annotations.add_line_highlight(Rc::new([LineHighlight {
+            anchor_char_idx: 5,
+            highlight: Highlight(2),
+        }]));
+
+        // unlike `overlay_at`/`next_inline_annotation_at`, this must not consume a cursor: the
+        // renderer re-queries the same anchor once per soft-wrapped visual row of its line.
+        assert_eq!(annotations.line_highlight_at(5).unwrap().0, 2);
+        assert_eq!(annotations.line_highlight_at(5).unwrap().0, 2);
+        assert!(annotations.line_highlight_at(0).is_none());
     }
 }
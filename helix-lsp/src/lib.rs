@@ -286,6 +286,12 @@ fn find_completion_range(text: RopeSlice, replace_mode: bool, cursor: usize) ->
         }
         (start, end)
     }
+    /// Resolves the range that a completion item replaces for a cursor. When the server sent an
+    /// explicit `edit_offset` (derived from its `text_edit`/`InsertReplaceEdit`), that range wins
+    /// even if it starts left of where the local word-boundary heuristic would have, since the
+    /// server may know about syntax the heuristic doesn't (e.g. replacing a leading '.' as part
+    /// of the edit). `find_completion_range` is only used as a fallback when the server didn't
+    /// provide a range at all.
     fn completion_range(
         text: RopeSlice,
         edit_offset: Option<(i128, i128)>,
@@ -951,7 +957,7 @@ pub fn find_lsp_workspace(
 #[cfg(test)]
 mod tests {
     use super::{lsp, util::*, OffsetEncoding};
-    use helix_core::Rope;
+    use helix_core::{Rope, Selection};
 
     #[test]
     fn converts_lsp_pos_to_pos() {
@@ -1016,4 +1022,30 @@ fn emoji_format_gh_4791() {
         let transaction = generate_transaction_from_edits(&source, edits, OffsetEncoding::Utf8);
         assert!(transaction.apply(&mut source));
     }
+
+    #[test]
+    fn completion_edit_offset_can_extend_left_of_the_word_boundary() {
+        // The local word-boundary heuristic (`find_completion_range`) would only see "bar" as
+        // the word under the cursor and start the replacement at the '.'s right edge. A server
+        // is free to return a `text_edit` range that starts further left than that, e.g. to
+        // also replace the '.' itself with `?.`. `edit_offset` must take priority over the local
+        // heuristic so that range is honored as-is.
+        let doc = Rope::from_str("foo.bar");
+        let cursor = doc.len_chars();
+        let selection = Selection::point(cursor);
+
+        // one char to the left of where `find_completion_range` would start (right after '.')
+        let edit_offset = Some((-4i128, 0i128));
+        let transaction = generate_transaction_from_completion_edit(
+            &doc,
+            &selection,
+            edit_offset,
+            false,
+            "?.baz".to_string(),
+        );
+
+        let mut result = doc.clone();
+        assert!(transaction.apply(&mut result));
+        assert_eq!(result, Rope::from_str("foo?.baz"));
+    }
 }
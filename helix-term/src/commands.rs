@@ -3167,16 +3167,29 @@ fn exclude_cursor(text: RopeSlice, range: Range, cursor: Range) -> Range {
 
     // It trigger completion when idle timer reaches deadline
     // Only trigger completion if the word under cursor is longer than n characters
+    /// Resolves the minimum word length that must precede the cursor before idle completion
+    /// triggers: `lang_override` (the current language's `completion_trigger_len`, if it sets
+    /// one) wins, falling back to the global `completion_trigger_len` config otherwise. Verbose
+    /// languages may want a longer minimum, terse ones a shorter one.
+    fn completion_trigger_len(lang_override: Option<u8>, global: u8) -> u8 {
+        lang_override.unwrap_or(global)
+    }
+
     pub fn idle_completion(cx: &mut Context) {
         let config = cx.editor.config();
         let (view, doc) = current!(cx.editor);
         let text = doc.text().slice(..);
         let cursor = doc.selection(view.id).primary().cursor(text);
 
+        let lang_override = doc
+            .language_config()
+            .and_then(|config| config.completion_trigger_len);
+        let trigger_len = completion_trigger_len(lang_override, config.completion_trigger_len);
+
         use helix_core::chars::char_is_word;
         let mut iter = text.chars_at(cursor);
         iter.reverse();
-        for _ in 0..config.completion_trigger_len {
+        for _ in 0..trigger_len {
             match iter.next() {
                 Some(c) if char_is_word(c) => {}
                 _ => return,
@@ -3185,32 +3198,70 @@ pub fn idle_completion(cx: &mut Context) {
         super::completion(cx);
     }
 
+    /// True if `ch` is one of `capabilities`' configured completion trigger characters.
+    /// Factored out of `is_completion_trigger_char` so the trigger-char matching itself can be
+    /// unit tested without an attached language server.
+    fn completion_trigger_chars_contain(
+        capabilities: &helix_lsp::lsp::ServerCapabilities,
+        ch: char,
+    ) -> bool {
+        use helix_lsp::lsp;
+
+        // TODO: what if trigger is multiple chars long
+        matches!(
+            &capabilities.completion_provider,
+            Some(lsp::CompletionOptions {
+                trigger_characters: Some(triggers),
+                ..
+            }) if triggers.iter().any(|trigger| trigger.contains(ch))
+        )
+    }
+
+    /// Shared by `language_server_completion` (after inserting a character) and
+    /// `retrigger_completion_after_accept` (after accepting a completion item): true if `ch` is
+    /// one of the active language server's configured completion trigger characters.
+    fn is_completion_trigger_char(doc: &Document, ch: char) -> bool {
+        let Some(language_server) = doc.language_server() else {
+            return false;
+        };
+
+        completion_trigger_chars_contain(language_server.capabilities(), ch)
+    }
+
     fn language_server_completion(cx: &mut Context, ch: char) {
         let config = cx.editor.config();
         if !config.auto_completion {
             return;
         }
 
-        use helix_lsp::lsp;
         // if ch matches completion char, trigger completion
         let doc = doc_mut!(cx.editor);
-        let language_server = match doc.language_server() {
-            Some(language_server) => language_server,
-            None => return,
-        };
+        if is_completion_trigger_char(doc, ch) {
+            cx.editor.clear_idle_timer();
+            super::completion(cx);
+        }
+    }
 
-        let capabilities = language_server.capabilities();
+    /// Called right after a completion item is accepted: if `retrigger_after_accept` is enabled
+    /// and the text that was just inserted ends in one of the language server's completion
+    /// trigger characters (e.g. accepting `foo.` leaves the cursor right after `.`), immediately
+    /// re-triggers completion for the next member instead of making the user type another
+    /// character, or wait for the idle timer, to see suggestions again.
+    pub(crate) fn retrigger_completion_after_accept(cx: &mut Context) {
+        if !cx.editor.config().retrigger_after_accept {
+            return;
+        }
 
-        if let Some(lsp::CompletionOptions {
-            trigger_characters: Some(triggers),
-            ..
-        }) = &capabilities.completion_provider
-        {
-            // TODO: what if trigger is multiple chars long
-            if triggers.iter().any(|trigger| trigger.contains(ch)) {
-                cx.editor.clear_idle_timer();
-                super::completion(cx);
-            }
+        let (view, doc) = current_ref!(cx.editor);
+        let text = doc.text().slice(..);
+        let cursor = doc.selection(view.id).primary().cursor(text);
+        let Some(ch) = cursor.checked_sub(1).and_then(|pos| text.get_char(pos)) else {
+            return;
+        };
+
+        let doc = doc_mut!(cx.editor);
+        if is_completion_trigger_char(doc, ch) {
+            super::completion(cx);
         }
     }
 
@@ -3543,6 +3594,61 @@ pub fn delete_word_forward(cx: &mut Context) {
 
         lsp::signature_help_impl(cx, SignatureHelpInvoked::Automatic);
     }
+
+    #[cfg(test)]
+    mod completion_trigger_tests {
+        use super::completion_trigger_chars_contain;
+        use helix_lsp::lsp;
+
+        fn capabilities_with_triggers(triggers: &[&str]) -> lsp::ServerCapabilities {
+            lsp::ServerCapabilities {
+                completion_provider: Some(lsp::CompletionOptions {
+                    trigger_characters: Some(triggers.iter().map(|s| s.to_string()).collect()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn accepted_text_ending_in_trigger_char_matches() {
+            // Simulates accepting an item whose insert text is `foo.`: the char just left of the
+            // cursor is `.`, which is one of the server's configured trigger characters, so
+            // `retrigger_completion_after_accept` would send a fresh trigger.
+            let capabilities = capabilities_with_triggers(&[".", "::"]);
+            assert!(completion_trigger_chars_contain(&capabilities, '.'));
+        }
+
+        #[test]
+        fn non_trigger_char_does_not_match() {
+            let capabilities = capabilities_with_triggers(&[".", "::"]);
+            assert!(!completion_trigger_chars_contain(&capabilities, 'x'));
+        }
+
+        #[test]
+        fn no_completion_provider_never_matches() {
+            let capabilities = lsp::ServerCapabilities::default();
+            assert!(!completion_trigger_chars_contain(&capabilities, '.'));
+        }
+    }
+
+    #[cfg(test)]
+    mod completion_trigger_len_tests {
+        use super::completion_trigger_len;
+
+        #[test]
+        fn language_override_wins_over_the_global_default() {
+            // e.g. a verbose language configured with a longer minimum than the global default.
+            assert_eq!(completion_trigger_len(Some(5), 2), 5);
+            // and a terse language configured with a shorter one.
+            assert_eq!(completion_trigger_len(Some(1), 2), 1);
+        }
+
+        #[test]
+        fn falls_back_to_the_global_default_when_unset() {
+            assert_eq!(completion_trigger_len(None, 2), 2);
+        }
+    }
 }
 
 // Undo / Redo
@@ -4198,11 +4304,99 @@ fn remove_primary_selection(cx: &mut Context) {
     doc.set_selection(view.id, selection);
 }
 
+/// Mirrors `lsp::CompletionResponse`/`lsp::CompletionList`, but additionally captures the
+/// response's `itemDefaults` object (LSP 3.17's `textDocument/completion` response field) that
+/// `lsp_types` 0.94 doesn't model on `CompletionList` at all. The `completion` callback below
+/// deserializes into this type instead of `lsp::CompletionResponse` so the field survives long
+/// enough to be applied - by the time a `cx.callback` closure runs, the underlying
+/// `serde_json::Value` is gone and only whatever `T` it was deserialized into remains.
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum CompletionResponse {
+    Array(Vec<helix_lsp::lsp::CompletionItem>),
+    List(CompletionList),
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionList {
+    is_incomplete: bool,
+    items: Vec<helix_lsp::lsp::CompletionItem>,
+    #[serde(default)]
+    item_defaults: Option<CompletionItemDefaults>,
+}
+
+/// Fallback values a server declares once for the whole response instead of repeating them on
+/// every item; applied onto items that don't set the corresponding field themselves, in
+/// `apply_item_defaults` below.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionItemDefaults {
+    #[serde(default)]
+    edit_range: Option<CompletionItemEditRange>,
+    #[serde(default)]
+    insert_text_format: Option<helix_lsp::lsp::InsertTextFormat>,
+    #[serde(default)]
+    commit_characters: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum CompletionItemEditRange {
+    Range(helix_lsp::lsp::Range),
+    InsertAndReplace {
+        insert: helix_lsp::lsp::Range,
+        replace: helix_lsp::lsp::Range,
+    },
+}
+
+/// Fills in `item`'s `text_edit`, `insert_text_format` and `commit_characters` from `defaults`
+/// wherever `item` didn't set its own - the LSP 3.17 `itemDefaults` mechanism servers use to avoid
+/// repeating the same edit range or insert format on every item in a list.
+fn apply_item_defaults(
+    item: &mut helix_lsp::lsp::CompletionItem,
+    defaults: &CompletionItemDefaults,
+) {
+    if item.text_edit.is_none() {
+        if let Some(edit_range) = &defaults.edit_range {
+            let new_text = item
+                .insert_text
+                .clone()
+                .unwrap_or_else(|| item.label.clone());
+            item.text_edit = Some(match edit_range {
+                CompletionItemEditRange::Range(range) => {
+                    helix_lsp::lsp::CompletionTextEdit::Edit(helix_lsp::lsp::TextEdit::new(
+                        *range, new_text,
+                    ))
+                }
+                CompletionItemEditRange::InsertAndReplace { insert, replace } => {
+                    helix_lsp::lsp::CompletionTextEdit::InsertAndReplace(
+                        helix_lsp::lsp::InsertReplaceEdit {
+                            new_text,
+                            insert: *insert,
+                            replace: *replace,
+                        },
+                    )
+                }
+            });
+        }
+    }
+    if item.insert_text_format.is_none() {
+        item.insert_text_format = defaults.insert_text_format;
+    }
+    if item.commit_characters.is_none() {
+        item.commit_characters = defaults.commit_characters.clone();
+    }
+}
+
 pub fn completion(cx: &mut Context) {
-    use helix_lsp::{lsp, util::pos_to_lsp_pos};
+    use helix_lsp::util::pos_to_lsp_pos;
 
     let (view, doc) = current!(cx.editor);
 
+    // `Document::language_server` returns a single server, so a completion request only ever
+    // queries one language server at a time - there is no step merging results from multiple
+    // servers (and therefore nothing to dedup across them) to hang an item-equality check off of.
     let language_server = match doc.language_server() {
         Some(language_server) => language_server,
         None => return,
@@ -4252,6 +4446,16 @@ pub fn completion(cx: &mut Context) {
     let trigger_doc = doc.id();
     let trigger_view = view.id;
 
+    // Captured now and compared against `Editor::completion_generation` in the callback below,
+    // so a request that's still in flight when the user re-triggers completion is discarded even
+    // if it's for the same view/doc/mode the staleness checks below also run.
+    cx.editor.completion_generation += 1;
+    let trigger_generation = cx.editor.completion_generation;
+
+    if cx.editor.config().completion_show_loading {
+        cx.editor.set_status("Requesting completions…");
+    }
+
     // FIXME: The commands Context can only have a single callback
     // which means it gets overwritten when executing keybindings
     // with multiple commands or macros. This would mean that completion
@@ -4268,7 +4472,7 @@ pub fn completion(cx: &mut Context) {
 
     cx.callback(
         future,
-        move |editor, compositor, response: Option<lsp::CompletionResponse>| {
+        move |editor, compositor, response: Option<CompletionResponse>| {
             let (view, doc) = current_ref!(editor);
             // check if the completion request is stale.
             //
@@ -4278,14 +4482,29 @@ pub fn completion(cx: &mut Context) {
             if editor.mode != Mode::Insert || view.id != trigger_view || doc.id() != trigger_doc {
                 return;
             }
+            if completion_response_is_stale(trigger_generation, editor.completion_generation) {
+                return;
+            }
+
+            if matches!(editor.get_status(), Some((msg, _)) if msg == "Requesting completions…") {
+                editor.status_msg = None;
+            }
 
             let items = match response {
-                Some(lsp::CompletionResponse::Array(items)) => items,
+                Some(CompletionResponse::Array(items)) => items,
                 // TODO: do something with is_incomplete
-                Some(lsp::CompletionResponse::List(lsp::CompletionList {
+                Some(CompletionResponse::List(CompletionList {
                     is_incomplete: _is_incomplete,
-                    items,
-                })) => items,
+                    mut items,
+                    item_defaults,
+                })) => {
+                    if let Some(defaults) = &item_defaults {
+                        items
+                            .iter_mut()
+                            .for_each(|item| apply_item_defaults(item, defaults));
+                    }
+                    items
+                }
                 None => Vec::new(),
             };
 
@@ -4317,6 +4536,148 @@ pub fn completion(cx: &mut Context) {
     );
 }
 
+/// True if a completion response captured at `response_generation` should be discarded because
+/// `Editor::completion_generation` has since moved on - i.e. a later trigger fired while this
+/// response was still in flight. Factored out of the callback above so the race it guards against
+/// (two triggers firing for the same view/doc/mode, which the other staleness checks wouldn't
+/// catch) can be exercised without spinning up a language server.
+fn completion_response_is_stale(response_generation: u64, current_generation: u64) -> bool {
+    response_generation != current_generation
+}
+
+#[cfg(test)]
+mod completion_generation_tests {
+    use super::completion_response_is_stale;
+
+    #[test]
+    fn second_trigger_supersedes_the_first_even_for_the_same_view_and_doc() {
+        // Trigger 1 fires, then trigger 2 fires (e.g. the user kept typing) before trigger 1's
+        // response comes back. Only the latest trigger's response should be applied.
+        let trigger_1_generation = 1;
+        let trigger_2_generation = 2;
+        let current_generation = trigger_2_generation;
+
+        assert!(completion_response_is_stale(
+            trigger_1_generation,
+            current_generation
+        ));
+        assert!(!completion_response_is_stale(
+            trigger_2_generation,
+            current_generation
+        ));
+    }
+}
+
+#[cfg(test)]
+mod completion_item_defaults_tests {
+    use super::{apply_item_defaults, CompletionResponse};
+    use helix_lsp::lsp;
+
+    #[test]
+    fn a_list_with_item_defaults_deserializes_from_the_raw_lsp_response() {
+        // `lsp_types::CompletionList` has no `item_defaults` field to deserialize this onto - see
+        // the doc comment on `CompletionResponse` above - so this exercises deserializing straight
+        // off the shape a server actually sends.
+        let response: CompletionResponse = serde_json::from_value(serde_json::json!({
+            "isIncomplete": false,
+            "itemDefaults": {
+                "editRange": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 3 }
+                },
+                "insertTextFormat": 2,
+                "commitCharacters": ["."]
+            },
+            "items": [
+                { "label": "foo" },
+                {
+                    "label": "bar",
+                    "textEdit": {
+                        "range": {
+                            "start": { "line": 1, "character": 0 },
+                            "end": { "line": 1, "character": 3 }
+                        },
+                        "newText": "bar"
+                    },
+                    "insertTextFormat": 1
+                }
+            ]
+        }))
+        .unwrap();
+
+        let CompletionResponse::List(list) = response else {
+            panic!("expected a `List` response");
+        };
+        assert_eq!(list.items.len(), 2);
+        assert!(list.item_defaults.is_some());
+    }
+
+    #[test]
+    fn item_defaults_only_fill_in_fields_an_item_did_not_set_itself() {
+        let response: CompletionResponse = serde_json::from_value(serde_json::json!({
+            "isIncomplete": false,
+            "itemDefaults": {
+                "editRange": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 3 }
+                },
+                "insertTextFormat": 2,
+                "commitCharacters": ["."]
+            },
+            "items": [
+                { "label": "foo" },
+                {
+                    "label": "bar",
+                    "textEdit": {
+                        "range": {
+                            "start": { "line": 1, "character": 0 },
+                            "end": { "line": 1, "character": 3 }
+                        },
+                        "newText": "bar"
+                    },
+                    "insertTextFormat": 1
+                }
+            ]
+        }))
+        .unwrap();
+
+        let CompletionResponse::List(mut list) = response else {
+            panic!("expected a `List` response");
+        };
+        let defaults = list.item_defaults.take().unwrap();
+        for item in &mut list.items {
+            apply_item_defaults(item, &defaults);
+        }
+
+        // `foo` set none of these fields itself, so it picks up all three defaults.
+        let foo = &list.items[0];
+        assert_eq!(
+            foo.text_edit,
+            Some(lsp::CompletionTextEdit::Edit(lsp::TextEdit::new(
+                lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(0, 3)),
+                "foo".to_string(),
+            )))
+        );
+        assert_eq!(
+            foo.insert_text_format,
+            Some(lsp::InsertTextFormat::SNIPPET)
+        );
+        assert_eq!(foo.commit_characters, Some(vec![".".to_string()]));
+
+        // `bar` already set its own edit and format, so the defaults must not overwrite them.
+        let bar = &list.items[1];
+        assert_eq!(
+            bar.text_edit,
+            Some(lsp::CompletionTextEdit::Edit(lsp::TextEdit::new(
+                lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 3)),
+                "bar".to_string(),
+            )))
+        );
+        assert_eq!(bar.insert_text_format, Some(lsp::InsertTextFormat::PLAIN_TEXT));
+        assert_eq!(bar.commit_characters, Some(vec![".".to_string()]));
+    }
+}
+
 // comments
 fn toggle_comments(cx: &mut Context) {
     let (view, doc) = current!(cx.editor);
@@ -2005,6 +2005,39 @@ fn open_log(
     Ok(())
 }
 
+fn messages(
+    cx: &mut compositor::Context,
+    _args: &[Cow<str>],
+    event: PromptEvent,
+) -> anyhow::Result<()> {
+    if event != PromptEvent::Validate {
+        return Ok(());
+    }
+
+    let mut contents = String::new();
+    for (message, severity) in cx.editor.status_history() {
+        writeln!(contents, "{:?}: {}", severity, message)?;
+    }
+    if contents.is_empty() {
+        contents.push_str("No messages yet.");
+    }
+
+    let callback = async move {
+        let call: job::Callback = Callback::EditorCompositor(Box::new(
+            move |editor: &mut Editor, compositor: &mut Compositor| {
+                let contents = ui::Markdown::new(contents, editor.syn_loader.clone());
+                let popup = Popup::new("messages", contents).auto_close(true);
+                compositor.replace_or_push("messages", popup);
+            },
+        ));
+        Ok(call)
+    };
+
+    cx.jobs.callback(callback);
+
+    Ok(())
+}
+
 fn refresh_config(
     cx: &mut compositor::Context,
     _args: &[Cow<str>],
@@ -2710,6 +2743,13 @@ fn clear_register(
             fun: open_log,
             signature: CommandSignature::none(),
         },
+        TypableCommand {
+            name: "messages",
+            aliases: &["msg"],
+            doc: "Show recent status-line messages (see `status-history-size`).",
+            fun: messages,
+            signature: CommandSignature::none(),
+        },
         TypableCommand {
             name: "insert-output",
             aliases: &[],
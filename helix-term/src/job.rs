@@ -1,3 +1,16 @@
+//! Async code that wants to touch editor state from a tokio task has exactly one door in,
+//! `Jobs::callback`: it queues a closure that runs the next time `Jobs::next_job` is awaited.
+//! [`Priority::High`] jobs are polled ahead of [`Priority::Normal`] ones by the `biased` select in
+//! `Application::event_loop_until_idle`, so a `High` job that resolves in the same wakeup as a
+//! `Normal` one is guaranteed to have its callback applied before the next render; every job
+//! defaults to `Normal`, and [`Job::with_priority`] (or [`Jobs::callback_high_priority`]) opts in
+//! to `High`.
+//!
+//! This tree has no `helix-event`/hooks crate - no `Hook`/`AsyncHook` trait, event registry, or
+//! `dispatch` loop - so tickets that assume one (priority-ordered or one-shot hooks, dispatch
+//! propagation control, typed dynamic hooks, per-hook panic isolation, and similar) are blocked on
+//! that subsystem rather than fixable here.
+
 use helix_view::Editor;
 
 use crate::compositor::Compositor;
@@ -15,15 +28,32 @@ pub enum Callback {
 
 pub type JobFuture = BoxFuture<'static, anyhow::Result<Option<Callback>>>;
 
+/// How eagerly a job's callback should be applied relative to other queued jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    /// Applied before any `Normal` job whose future also happened to resolve this wakeup - e.g.
+    /// dismissing a popup, where leaving the stale popup on screen for one extra frame would be
+    /// visible to the user.
+    High,
+    /// Applied whenever the executor gets to it - the right default for background work like
+    /// indexing that has no rendering deadline.
+    #[default]
+    Normal,
+}
+
 pub struct Job {
     pub future: BoxFuture<'static, anyhow::Result<Option<Callback>>>,
     /// Do we need to wait for this job to finish before exiting?
     pub wait: bool,
+    pub priority: Priority,
 }
 
 #[derive(Default)]
 pub struct Jobs {
     pub futures: FuturesUnordered<JobFuture>,
+    /// Polled ahead of `futures` by `Application::event_loop_until_idle`'s `biased` select; see
+    /// [`Priority::High`].
+    pub high_priority_futures: FuturesUnordered<JobFuture>,
     /// These are the ones that need to complete before we exit.
     pub wait_futures: FuturesUnordered<JobFuture>,
 }
@@ -33,6 +63,7 @@ pub fn new<F: Future<Output = anyhow::Result<()>> + Send + 'static>(f: F) -> Sel
         Self {
             future: f.map(|r| r.map(|()| None)).boxed(),
             wait: false,
+            priority: Priority::default(),
         }
     }
 
@@ -42,6 +73,7 @@ pub fn with_callback<F: Future<Output = anyhow::Result<Callback>> + Send + 'stat
         Self {
             future: f.map(|r| r.map(Some)).boxed(),
             wait: false,
+            priority: Priority::default(),
         }
     }
 
@@ -49,6 +81,11 @@ pub fn wait_before_exiting(mut self) -> Self {
         self.wait = true;
         self
     }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 impl Jobs {
@@ -67,6 +104,15 @@ pub fn callback<F: Future<Output = anyhow::Result<Callback>> + Send + 'static>(
         self.add(Job::with_callback(f));
     }
 
+    /// Like [`Self::callback`], but the callback is applied before any `Normal`-priority job
+    /// that resolves in the same wakeup - see [`Priority::High`].
+    pub fn callback_high_priority<F: Future<Output = anyhow::Result<Callback>> + Send + 'static>(
+        &mut self,
+        f: F,
+    ) {
+        self.add(Job::with_callback(f).with_priority(Priority::High));
+    }
+
     pub fn handle_callback(
         &self,
         editor: &mut Editor,
@@ -87,7 +133,12 @@ pub fn handle_callback(
 
     pub async fn next_job(&mut self) -> Option<anyhow::Result<Option<Callback>>> {
         tokio::select! {
-            event = self.futures.next() => {  event }
+            // `biased` disables `select!`'s default random branch selection: when more than one
+            // branch is ready in the same wakeup, the first ready branch listed wins instead of
+            // picking uniformly at random. That's what gives `Priority::High` its guarantee.
+            biased;
+            event = self.high_priority_futures.next() => { event }
+            event = self.futures.next() => { event }
             event = self.wait_futures.next() => { event }
         }
     }
@@ -95,6 +146,8 @@ pub async fn next_job(&mut self) -> Option<anyhow::Result<Option<Callback>>> {
     pub fn add(&self, j: Job) {
         if j.wait {
             self.wait_futures.push(j.future);
+        } else if j.priority == Priority::High {
+            self.high_priority_futures.push(j.future);
         } else {
             self.futures.push(j.future);
         }
@@ -138,3 +191,46 @@ pub async fn finish(
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use futures_util::stream::StreamExt;
+
+    use super::{Job, Jobs, Priority};
+
+    #[tokio::test]
+    async fn high_priority_jobs_are_applied_before_normal_ones_queued_the_same_tick() {
+        let mut jobs = Jobs::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let recorder = order.clone();
+        jobs.add(Job::new(async move {
+            recorder.lock().unwrap().push("normal");
+            Ok(())
+        }));
+        let recorder = order.clone();
+        jobs.add(
+            Job::new(async move {
+                recorder.lock().unwrap().push("high");
+                Ok(())
+            })
+            .with_priority(Priority::High),
+        );
+
+        // Mirrors the `biased` select in `Application::event_loop_until_idle`: both jobs are
+        // ready on the very first poll, so draining them one `select!` at a time must always
+        // observe `high` before `normal`, never the reverse.
+        for _ in 0..2 {
+            tokio::select! {
+                biased;
+                Some(_) = jobs.high_priority_futures.next() => {}
+                Some(_) = jobs.futures.next() => {}
+                else => break,
+            }
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["high", "normal"]);
+    }
+}
@@ -6,20 +6,35 @@
     ViewId,
 };
 use tui::{buffer::Buffer as Surface, text::Span};
+#[cfg(test)]
+use tui::text::Text;
 
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicU64, Arc},
+};
 
 use helix_core::{Change, Transaction};
 use helix_view::{graphics::Rect, Document, Editor};
 
 use crate::commands;
+use crate::ctrl;
 use crate::ui::{menu, Markdown, Menu, Popup, PromptEvent};
 
 use helix_lsp::{lsp, util};
 use lsp::CompletionItem;
 
+/// Resolved theme styles for [`CompletionItem`] rendering, computed once from `editor.theme` in
+/// [`Completion::new`] rather than looked up per-row.
+pub struct CompletionItemStyles {
+    /// Style for a deprecated/obsolete item's label, from `ui.completion.deprecated`. Strikethrough
+    /// by default so a theme that doesn't define the scope still gets a visible marker; a theme
+    /// can override or add to that via the scope itself.
+    deprecated: Style,
+}
+
 impl menu::Item for CompletionItem {
-    type Data = ();
+    type Data = CompletionItemStyles;
     fn sort_text(&self, data: &Self::Data) -> Cow<str> {
         self.filter_text(data)
     }
@@ -33,7 +48,7 @@ fn filter_text(&self, _data: &Self::Data) -> Cow<str> {
             .into()
     }
 
-    fn format(&self, _data: &Self::Data) -> menu::Row {
+    fn format(&self, data: &Self::Data) -> menu::Row {
         let deprecated = self.deprecated.unwrap_or_default()
             || self.tags.as_ref().map_or(false, |tags| {
                 tags.contains(&lsp::CompletionItemTag::DEPRECATED)
@@ -42,7 +57,7 @@ fn format(&self, _data: &Self::Data) -> menu::Row {
             menu::Cell::from(Span::styled(
                 self.label.as_str(),
                 if deprecated {
-                    Style::default().add_modifier(Modifier::CROSSED_OUT)
+                    data.deprecated
                 } else {
                     Style::default()
                 },
@@ -88,6 +103,58 @@ fn format(&self, _data: &Self::Data) -> menu::Row {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ui::menu::Item as _;
+
+    #[test]
+    fn format_applies_the_deprecated_style_only_to_deprecated_items() {
+        let styles = CompletionItemStyles {
+            deprecated: Style::default().add_modifier(Modifier::CROSSED_OUT),
+        };
+
+        let mut item = CompletionItem::new_simple("old_fn".to_string(), String::new());
+        item.deprecated = Some(true);
+        let row = item.format(&styles);
+        assert_eq!(
+            row.cells[0].content,
+            Text::from(Span::styled("old_fn", styles.deprecated))
+        );
+
+        let item = CompletionItem::new_simple("new_fn".to_string(), String::new());
+        let row = item.format(&styles);
+        assert_eq!(
+            row.cells[0].content,
+            Text::from(Span::styled("new_fn", Style::default()))
+        );
+    }
+}
+
+/// Orders `CompletionItemKind`s for `completion_group_by_kind`, roughly most-to-least likely to
+/// be what the user is typing. Unknown kinds (including `None`) sort last.
+fn kind_sort_rank(kind: Option<lsp::CompletionItemKind>) -> u32 {
+    match kind {
+        Some(lsp::CompletionItemKind::VARIABLE) => 0,
+        Some(lsp::CompletionItemKind::FIELD) => 1,
+        Some(lsp::CompletionItemKind::PROPERTY) => 2,
+        Some(lsp::CompletionItemKind::FUNCTION) => 3,
+        Some(lsp::CompletionItemKind::METHOD) => 4,
+        Some(lsp::CompletionItemKind::CONSTRUCTOR) => 5,
+        Some(lsp::CompletionItemKind::CLASS) => 6,
+        Some(lsp::CompletionItemKind::STRUCT) => 7,
+        Some(lsp::CompletionItemKind::INTERFACE) => 8,
+        Some(lsp::CompletionItemKind::MODULE) => 9,
+        Some(lsp::CompletionItemKind::ENUM) => 10,
+        Some(lsp::CompletionItemKind::ENUM_MEMBER) => 11,
+        Some(lsp::CompletionItemKind::CONSTANT) => 12,
+        Some(lsp::CompletionItemKind::KEYWORD) => 13,
+        Some(lsp::CompletionItemKind::SNIPPET) => 14,
+        Some(_) => 15,
+        None => 16,
+    }
+}
+
 /// Wraps a Menu.
 pub struct Completion {
     popup: Popup<Menu<CompletionItem>>,
@@ -95,6 +162,24 @@ pub struct Completion {
     #[allow(dead_code)]
     trigger_offset: usize,
     // TODO: maintain a completioncontext with trigger kind & trigger char
+    /// Bumped every time a `completionItem/resolve` request is issued; a response only gets
+    /// applied if this still matches the generation it was issued under. This tree has no
+    /// `CancelTx`-style request cancellation, so a superseded resolve still completes on the
+    /// wire — its result is just discarded instead of being applied to a stale selection.
+    resolve_generation: Arc<AtomicU64>,
+    /// Scroll offset into the documentation popup, in the same units as `Popup::scroll`. Reset
+    /// to 0 whenever `documentation_index` no longer matches the menu's current selection, so
+    /// the user keeps their place while reading and only loses it by actually moving on.
+    documentation_scroll: usize,
+    /// The menu selection index the documentation popup was last rendered for; see
+    /// `documentation_scroll`. `None` before anything has been rendered yet.
+    documentation_index: Option<usize>,
+    /// The height of the documentation popup as of the last render, along with the maximum
+    /// value `documentation_scroll` may take without scrolling past the end of the content.
+    /// `None` when nothing was rendered last frame (no selection, or no detail/documentation to
+    /// show) - in that case `ctrl-d`/`ctrl-u` fall back to scrolling the completion menu instead
+    /// of the (absent) documentation popup; see `handle_event`.
+    documentation_size: Option<(u16, usize)>,
 }
 
 impl Completion {
@@ -109,11 +194,23 @@ pub fn new(
         trigger_offset: usize,
     ) -> Self {
         let replace_mode = editor.config().completion_replace;
-        // Sort completion items according to their preselect status (given by the LSP server)
-        items.sort_by_key(|item| !item.preselect.unwrap_or(false));
+        if editor.config().completion_group_by_kind {
+            // Cluster items of the same kind together, preselected items first within a
+            // cluster. `sort_by_key` is stable so items keep their relative LSP response order
+            // (and therefore their fuzzy-match tie-break order) within a group.
+            items.sort_by_key(|item| (kind_sort_rank(item.kind), !item.preselect.unwrap_or(false)));
+        } else {
+            // Sort completion items according to their preselect status (given by the LSP server)
+            items.sort_by_key(|item| !item.preselect.unwrap_or(false));
+        }
 
         // Then create the menu
-        let menu = Menu::new(items, (), move |editor: &mut Editor, item, event| {
+        let item_styles = CompletionItemStyles {
+            deprecated: Style::default()
+                .add_modifier(Modifier::CROSSED_OUT)
+                .patch(editor.theme.get("ui.completion.deprecated")),
+        };
+        let menu = Menu::new(items, item_styles, move |editor: &mut Editor, item, event| {
             fn item_to_transaction(
                 doc: &Document,
                 view_id: ViewId,
@@ -295,9 +392,19 @@ fn completion_changes(transaction: &Transaction, trigger_offset: usize) -> Vec<C
             popup,
             start_offset,
             trigger_offset,
+            resolve_generation: Arc::new(AtomicU64::new(0)),
+            documentation_scroll: 0,
+            documentation_index: None,
+            documentation_size: None,
         };
 
-        // need to recompute immediately in case start_offset != trigger_offset
+        // Need to recompute immediately in case start_offset != trigger_offset. Note that this
+        // also transparently carries forward whatever the user typed between issuing the
+        // completion request and this response arriving (e.g. re-requesting on a trigger
+        // character, then typing a few more letters of the member name before the language
+        // server replies): `recompute_filter` reads the live document from `start_offset` to
+        // the current cursor rather than some snapshot of the filter text, so there is no
+        // separate filter state to thread through the request/response round-trip.
         completion.recompute_filter(editor);
 
         completion
@@ -390,6 +497,24 @@ pub fn ensure_item_resolved(&mut self, cx: &mut commands::Context) -> bool {
             None => return false,
         };
 
+        // Debounce: only the item the selection has settled on actually gets resolved. Quickly
+        // arrowing past several items bumps `resolve_generation` past each of their requests in
+        // turn, so by the time this sleep elapses only the last one still matches and fires.
+        use std::sync::atomic::Ordering;
+        let generation = self.resolve_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let resolve_generation = self.resolve_generation.clone();
+        let future = async move {
+            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+            if resolve_generation.load(Ordering::SeqCst) != generation {
+                return Ok(serde_json::Value::Null);
+            }
+            let response = future.await;
+            if resolve_generation.load(Ordering::SeqCst) != generation {
+                return Ok(serde_json::Value::Null);
+            }
+            response
+        };
+
         cx.callback(
             future,
             move |_editor, compositor, response: Option<lsp::CompletionItem>| {
@@ -418,6 +543,26 @@ pub fn area(&mut self, viewport: Rect, editor: &Editor) -> Rect {
 
 impl Component for Completion {
     fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
+        // `Popup::handle_event` already claims `ctrl-d`/`ctrl-u` for scrolling the item menu, so
+        // only steal them here when a documentation popup is actually on screen this frame -
+        // otherwise the keys keep their existing meaning as before this feature existed.
+        if let (Some((height, max_scroll)), Event::Key(key)) = (self.documentation_size, event) {
+            let half_page = height as usize / 2;
+            match *key {
+                ctrl!('d') => {
+                    self.documentation_scroll =
+                        (self.documentation_scroll + half_page).min(max_scroll);
+                    return EventResult::Consumed(None);
+                }
+                ctrl!('u') => {
+                    self.documentation_scroll =
+                        self.documentation_scroll.saturating_sub(half_page);
+                    return EventResult::Consumed(None);
+                }
+                _ => {}
+            }
+        }
+
         self.popup.handle_event(event, cx)
     }
 
@@ -428,11 +573,21 @@ fn required_size(&mut self, viewport: (u16, u16)) -> Option<(u16, u16)> {
     fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
         self.popup.render(area, surface, cx);
 
+        // Nothing is drawn below this point on most paths (no selection, no detail/docs to
+        // show, or not enough room) - default to "no documentation popup this frame" up front
+        // so every early return below leaves `documentation_size` correct for `handle_event`.
+        self.documentation_size = None;
+
         // if we have a selection, render a markdown popup on top/below with info
+        let selection_index = self.popup.contents().cursor();
         let option = match self.popup.contents().selection() {
             Some(option) => option,
             None => return,
         };
+        if self.documentation_index != selection_index {
+            self.documentation_scroll = 0;
+            self.documentation_index = selection_index;
+        }
         // need to render:
         // option.detail
         // ---
@@ -522,6 +677,17 @@ fn render(&mut self, area: Rect, surface: &mut Surface, cx: &mut Context) {
             Rect::new(0, y, area.width, avail_height.min(15))
         };
 
+        // `required_size` reports the content's true height regardless of `doc_area`, which may
+        // have been clamped to fit the available space above - that's what a scroll offset would
+        // actually be revealing, so it's what bounds how far `ctrl-d` is allowed to go.
+        let content_height = markdown_doc
+            .required_size((doc_area.width, doc_area.height))
+            .map_or(doc_area.height, |(_, height)| height);
+        let max_scroll = content_height.saturating_sub(doc_area.height) as usize;
+        self.documentation_scroll = self.documentation_scroll.min(max_scroll);
+        self.documentation_size = Some((doc_area.height, max_scroll));
+        cx.scroll = Some(self.documentation_scroll);
+
         // clear area
         let background = cx.editor.theme.get("ui.popup");
         surface.clear_with(doc_area, background);
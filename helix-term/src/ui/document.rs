@@ -1,4 +1,5 @@
 use std::cmp::min;
+use std::str;
 
 use helix_core::doc_formatter::{DocumentFormatter, GraphemeSource, TextFormat};
 use helix_core::graphemes::Grapheme;
@@ -15,6 +16,15 @@
 use helix_view::Theme;
 use tui::buffer::Buffer as Surface;
 
+#[cfg(test)]
+mod test;
+
+/// A hook that draws extra background/foreground content on top of a rendered line (cursorline
+/// highlight, gutter overlays, and similar). There is no `DecorationManager` collecting these -
+/// callers build a plain `Vec<Box<dyn LineDecoration>>` themselves and `render_text` runs it in
+/// that order, so controlling draw order (e.g. diagnostics over inlay hints) is already just a
+/// matter of the order the caller pushes onto the vec, with no separate `z`/`DecorationRenderIdx`
+/// concept to introduce.
 pub trait LineDecoration {
     fn render_background(&mut self, _renderer: &mut TextRenderer, _pos: LinePos) {}
     fn render_foreground(
@@ -162,6 +172,15 @@ pub fn render_text<'t>(
     line_decorations: &mut [Box<dyn LineDecoration + '_>],
     translated_positions: &mut [TranslatedPosition],
 ) {
+    // A collapsed split (momentarily zero-sized during a resize) has nothing to draw into.
+    // The main loop below already breaks out before drawing anything once `pos.row` reaches
+    // `viewport.height`, so a height of 0 happens to already be handled safely, but bailing out
+    // up front here makes that guarantee explicit rather than incidental, and also covers a
+    // zero-width viewport, which the loop doesn't special-case at all.
+    if renderer.viewport.height == 0 || renderer.viewport.width == 0 {
+        return;
+    }
+
     let (
         Position {
             row: mut row_off, ..
@@ -203,7 +222,7 @@ pub fn render_text<'t>(
         let doc_line = formatter.line_pos();
         let Some((grapheme, mut pos)) = formatter.next() else {
             let mut last_pos = formatter.visual_pos();
-            if last_pos.row >= row_off {
+            let first_blank_row = if last_pos.row >= row_off {
                 last_pos.col -= 1;
                 last_pos.row -= row_off;
                 // check if any positions translated on the fly (like cursor) are at the EOF
@@ -215,7 +234,12 @@ pub fn render_text<'t>(
                     renderer,
                     last_pos,
                 );
-            }
+                last_pos.row as u16 + 1
+            } else {
+                // the whole document scrolled past the top of the viewport
+                0
+            };
+            renderer.draw_end_of_buffer(first_blank_row);
             break;
         };
 
@@ -254,6 +278,20 @@ pub fn render_text<'t>(
                 visual_line: pos.row as u16,
                 start_char_idx: char_pos,
             };
+            // Applied before `line_decorations` so a caller-supplied decoration (e.g. the
+            // cursorline) draws on top of it rather than being painted over.
+            if let Some(highlight) = text_annotations.line_highlight_at(text.line_to_char(doc_line))
+            {
+                renderer.surface.set_style(
+                    Rect::new(
+                        renderer.viewport.x,
+                        renderer.viewport.y + last_line_pos.visual_line,
+                        renderer.viewport.width,
+                        1,
+                    ),
+                    renderer.text_style.patch(theme.highlight(highlight.0)),
+                );
+            }
             for line_decoration in &mut *line_decorations {
                 line_decoration.render_background(renderer, last_line_pos);
             }
@@ -282,6 +320,10 @@ pub fn render_text<'t>(
             } else {
                 style
             }
+        } else if grapheme.is_eof() {
+            renderer.eof_style
+        } else if grapheme.grapheme.is_control_char() {
+            renderer.control_char_style
         } else {
             style_span.0
         };
@@ -303,11 +345,37 @@ pub fn render_text<'t>(
     }
 }
 
+/// Computes the first indent level whose guide should be drawn.
+///
+/// `col_offset / indent_width` (rounded up) skips levels that have been scrolled off the left
+/// edge - drawing one would underflow `x` in `draw_indent_guides`, since `i * indent_width <
+/// col_offset` there. `skip_levels` is the user's separate preference to additionally hide that
+/// many outermost levels. `render_level_0` lets a user override whether the very first level (0)
+/// participates in that `skip_levels` count specifically, independent of its value: `Some(true)`
+/// always includes it (as long as it's still on screen), `Some(false)` always excludes it even if
+/// `skip_levels` is 0, and `None` leaves `skip_levels` as the sole decider, matching the pre-
+/// existing behavior.
+fn indent_guide_starting_level(
+    col_offset: usize,
+    indent_width: usize,
+    skip_levels: usize,
+    render_level_0: Option<bool>,
+) -> usize {
+    let scroll_skip = col_offset / indent_width + (col_offset % indent_width != 0) as usize;
+    match render_level_0 {
+        Some(true) => scroll_skip,
+        Some(false) => (scroll_skip + skip_levels).max(1),
+        None => scroll_skip + skip_levels,
+    }
+}
+
 #[derive(Debug)]
 pub struct TextRenderer<'a> {
     pub surface: &'a mut Surface,
     pub text_style: Style,
     pub whitespace_style: Style,
+    pub eof_style: Style,
+    pub control_char_style: Style,
     pub indent_guide_char: String,
     pub indent_guide_style: Style,
     pub newline: String,
@@ -318,6 +386,7 @@ pub struct TextRenderer<'a> {
     pub indent_width: u16,
     pub starting_indent: usize,
     pub draw_indent_guides: bool,
+    pub end_of_buffer_char: Option<char>,
     pub col_offset: usize,
     pub viewport: Rect,
 }
@@ -375,10 +444,17 @@ pub fn new(
             tab,
             virtual_tab,
             whitespace_style: theme.get("ui.virtual.whitespace"),
+            eof_style: theme.try_get("ui.virtual.eof").unwrap_or(text_style),
+            control_char_style: theme
+                .try_get("ui.virtual.control-char")
+                .unwrap_or(text_style),
             indent_width,
-            starting_indent: col_offset / indent_width as usize
-                + (col_offset % indent_width as usize != 0) as usize
-                + editor_config.indent_guides.skip_levels as usize,
+            starting_indent: indent_guide_starting_level(
+                col_offset,
+                indent_width as usize,
+                editor_config.indent_guides.skip_levels as usize,
+                editor_config.indent_guides.render_level_0,
+            ),
             indent_guide_style: text_style.patch(
                 theme
                     .try_get("ui.virtual.indent-guide")
@@ -386,6 +462,7 @@ pub fn new(
             ),
             text_style,
             draw_indent_guides: editor_config.indent_guides.render,
+            end_of_buffer_char: editor_config.end_of_buffer_char,
             viewport,
             col_offset,
         }
@@ -417,6 +494,7 @@ pub fn draw_grapheme(
         } else {
             &self.tab
         };
+        let control_char_buf;
         let grapheme = match grapheme {
             Grapheme::Tab { width } => {
                 let grapheme_tab_width = char_to_byte_idx(tab, width);
@@ -427,6 +505,11 @@ pub fn draw_grapheme(
             Grapheme::Other { ref g } if g == "\u{00A0}" => nbsp,
             Grapheme::Other { ref g } => g,
             Grapheme::Newline => &self.newline,
+            Grapheme::ControlChar { code } => {
+                let second = if code == 0x7F { b'?' } else { code + 0x40 };
+                control_char_buf = [b'^', second];
+                str::from_utf8(&control_char_buf).unwrap()
+            }
         };
 
         let in_bounds = self.col_offset <= position.col
@@ -456,6 +539,29 @@ pub fn draw_grapheme(
         }
     }
 
+    /// Patches the style of the cell at `(col, row)` without touching its symbol - the
+    /// clipping-aware primitive decorations that only want to tint a background cell (rulers,
+    /// cursorline) should reach for instead of going around this renderer to `self.surface`
+    /// directly, which risks writing outside the viewport.
+    ///
+    /// `col`/`row` are in the same coordinate space as `draw_grapheme`'s `position`: `col` is a
+    /// document column (before `col_offset` is subtracted) and `row` is relative to the top of
+    /// the viewport. A cell scrolled off either edge - horizontally past `col_offset` or past the
+    /// right/bottom of the viewport - is silently skipped rather than patched.
+    pub fn patch_cell_style(&mut self, col: usize, row: usize, style: Style) {
+        let in_bounds = self.col_offset <= col
+            && col < self.viewport.width as usize + self.col_offset
+            && row < self.viewport.height as usize;
+
+        if in_bounds {
+            let x = self.viewport.x + (col - self.col_offset) as u16;
+            let y = self.viewport.y + row as u16;
+            if let Some(cell) = self.surface.get_mut(x, y) {
+                cell.set_style(style);
+            }
+        }
+    }
+
     /// Overlay indentation guides ontop of a rendered line
     /// The indentation level is computed in `draw_lines`.
     /// Therefore this function must always be called afterwards.
@@ -481,4 +587,22 @@ pub fn draw_indent_guides(&mut self, indent_level: usize, row: u16) {
                 .set_string(x, y, &self.indent_guide_char, self.indent_guide_style);
         }
     }
+
+    /// Fills every viewport row from `first_blank_row` onwards with `eof_style`, and - if
+    /// `end_of_buffer_char` is configured - draws it at the start of each of those rows. Called
+    /// once the document has run out of lines before the viewport has run out of rows, so those
+    /// rows are explicitly rendered rather than left as whatever the surface already held.
+    pub fn draw_end_of_buffer(&mut self, first_blank_row: u16) {
+        for row in first_blank_row..self.viewport.height {
+            let y = self.viewport.y + row;
+            self.surface.set_style(
+                Rect::new(self.viewport.x, y, self.viewport.width, 1),
+                self.eof_style,
+            );
+            if let Some(ch) = self.end_of_buffer_char {
+                self.surface
+                    .set_string(self.viewport.x, y, ch.to_string(), self.eof_style);
+            }
+        }
+    }
 }
@@ -0,0 +1,140 @@
+use super::{indent_guide_starting_level, render_document, TextRenderer};
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use helix_core::syntax::Highlight;
+use helix_core::text_annotations::{LineHighlight, TextAnnotations};
+use helix_core::Rope;
+use helix_view::document::Document;
+use helix_view::editor::Config;
+use helix_view::graphics::Rect;
+use helix_view::theme::{Color, Style, Theme};
+use helix_view::view::ViewPosition;
+use tui::buffer::Buffer as Surface;
+
+#[test]
+fn level_0_follows_skip_levels_by_default_at_col_offset_0() {
+    assert_eq!(indent_guide_starting_level(0, 4, 0, None), 0);
+    assert_eq!(indent_guide_starting_level(0, 4, 2, None), 2);
+}
+
+#[test]
+fn render_level_0_overrides_skip_levels_at_col_offset_0() {
+    assert_eq!(indent_guide_starting_level(0, 4, 2, Some(true)), 0);
+    assert_eq!(indent_guide_starting_level(0, 4, 0, Some(false)), 1);
+    // skip_levels already excludes level 0, so forcing it off again changes nothing
+    assert_eq!(indent_guide_starting_level(0, 4, 2, Some(false)), 2);
+}
+
+#[test]
+fn scrolled_past_level_0_hides_it_regardless_of_the_override() {
+    // scrolled one full indent width past column 0: level 0 is off screen and would underflow
+    // the draw position, so even `Some(true)` can't bring it back.
+    assert_eq!(indent_guide_starting_level(4, 4, 0, None), 1);
+    assert_eq!(indent_guide_starting_level(4, 4, 0, Some(true)), 1);
+    assert_eq!(indent_guide_starting_level(4, 4, 0, Some(false)), 1);
+}
+
+#[test]
+fn render_document_fills_rows_below_a_short_document_with_end_of_buffer_char() {
+    let mut config = Config::default();
+    config.end_of_buffer_char = Some('~');
+    let doc = Document::from(
+        Rope::from("one\ntwo"),
+        None,
+        Arc::new(ArcSwap::new(Arc::new(config))),
+    );
+    let theme = Theme::default();
+    let viewport = Rect::new(0, 0, 10, 5);
+    let mut surface = Surface::empty(viewport);
+
+    render_document(
+        &mut surface,
+        viewport,
+        &doc,
+        ViewPosition::default(),
+        &TextAnnotations::default(),
+        std::iter::empty(),
+        &theme,
+        &mut [],
+        &mut [],
+    );
+
+    assert_eq!(surface.get(0, 0).unwrap().symbol, "o");
+    assert_eq!(surface.get(0, 1).unwrap().symbol, "t");
+    // every row below the document's two lines is filled with the configured marker
+    for row in 2..5 {
+        assert_eq!(surface.get(0, row).unwrap().symbol, "~");
+    }
+}
+
+#[test]
+fn line_highlight_paints_every_visual_row_of_its_anchor_line_but_no_others() {
+    let mut config = Config::default();
+    config.soft_wrap.enable = Some(true);
+    config.soft_wrap.max_wrap = Some(0);
+    let doc = Document::from(
+        Rope::from("foo bar foo bar\nbaz"),
+        None,
+        Arc::new(ArcSwap::new(Arc::new(config))),
+    );
+    let theme = Theme::from(toml::toml! { "diagnostic.error" = "red" }.into());
+    let red = theme.highlight(0);
+    assert_eq!(red.fg, Some(Color::Red));
+
+    // Soft wrap only applies to viewports wider than 10 columns (see `Document::text_format`);
+    // in a 12-column viewport with word-wrap disabled (`max_wrap = 0`) "foo bar foo bar" splits
+    // into two visual rows, so the highlight's reach past its own row is observable.
+    let viewport = Rect::new(0, 0, 12, 5);
+    let mut surface = Surface::empty(viewport);
+    let mut annotations = TextAnnotations::default();
+    annotations.add_line_highlight(Rc::new([LineHighlight {
+        anchor_char_idx: 0,
+        highlight: Highlight(0),
+    }]));
+
+    render_document(
+        &mut surface,
+        viewport,
+        &doc,
+        ViewPosition::default(),
+        &annotations,
+        std::iter::empty(),
+        &theme,
+        &mut [],
+        &mut [],
+    );
+
+    // Both visual rows produced by wrapping "foo bar foo bar" are colored...
+    assert_eq!(surface.get(0, 0).unwrap().style().fg, Some(Color::Red));
+    assert_eq!(surface.get(0, 1).unwrap().style().fg, Some(Color::Red));
+    // ...but the unrelated "baz" line is left alone.
+    assert_eq!(surface.get(0, 2).unwrap().style().fg, None);
+}
+
+#[test]
+fn patch_cell_style_clips_cells_scrolled_off_the_viewport() {
+    let doc = Document::from(
+        Rope::from("hello"),
+        None,
+        Arc::new(ArcSwap::new(Arc::new(Config::default()))),
+    );
+    let theme = Theme::default();
+    let viewport = Rect::new(0, 0, 10, 3);
+    let mut surface = Surface::empty(viewport);
+    let col_offset = 5;
+    let mut renderer = TextRenderer::new(&mut surface, &doc, &theme, col_offset, viewport);
+
+    let style = Style::default().fg(Color::Red);
+    // scrolled off to the left of `col_offset`
+    renderer.patch_cell_style(0, 0, style);
+    // past the bottom of the viewport
+    renderer.patch_cell_style(col_offset, 10, style);
+    // squarely inside the visible window
+    renderer.patch_cell_style(col_offset + 2, 0, style);
+
+    assert_eq!(surface.get(0, 0).unwrap().style(), Style::default());
+    assert_eq!(surface.get(2, 0).unwrap().style(), style);
+}
@@ -45,6 +45,8 @@ pub struct EditorView {
     spinners: ProgressSpinners,
 }
 
+/// Replayed against `insert_mode`/the completion popup to reconstruct the effects of the last
+/// insert-mode command (e.g. for dot-repeat).
 #[derive(Debug, Clone)]
 pub enum InsertEvent {
     Key(KeyEvent),
@@ -374,7 +376,22 @@ pub fn doc_diagnostics_highlights(
                     // diagnostic starts on range.start or later. If this assertion fails,
                     // we will discard some part of `diagnostic`. This implies that
                     // `doc.diagnostics()` is not sorted by `diagnostic.range`.
+                    //
+                    // `doc.diagnostics()` is sorted by the document itself, but diagnostics
+                    // ultimately originate from language servers, so a misbehaving server could
+                    // still hand us an unsorted list. debug_assert! alone would let a release
+                    // build silently merge the wrong ranges, so on top of the assert we also
+                    // skip (and log) the offending diagnostic in all builds rather than
+                    // corrupting the span it would have produced.
                     debug_assert!(range.start <= diagnostic.range.start);
+                    if diagnostic.range.start < range.start {
+                        log::warn!(
+                            "discarding out-of-order diagnostic {:?} (expected start >= {})",
+                            diagnostic.range,
+                            range.start
+                        );
+                        continue;
+                    }
                     range.end = diagnostic.range.end.max(range.end)
                 }
                 _ => vec.push((scope, diagnostic.range.start..diagnostic.range.end)),
@@ -427,7 +444,11 @@ pub fn doc_selection_highlights(
         }
         .unwrap_or(base_primary_cursor_scope);
 
-        let mut spans: Vec<(usize, std::ops::Range<usize>)> = Vec::new();
+        // All cursors/selections are folded into a single span list in one pass so that
+        // rendering documents with hundreds of cursors stays O(cursors) rather than
+        // O(cursors * decorations); pre-size for the worst case (cursor + selection span
+        // per range) to avoid reallocating while pushing.
+        let mut spans: Vec<(usize, std::ops::Range<usize>)> = Vec::with_capacity(selection.len() * 2);
         for (i, range) in selection.iter().enumerate() {
             let selection_is_primary = i == primary_idx;
             let (cursor_scope, selection_scope) = if selection_is_primary {
@@ -1276,10 +1297,31 @@ fn handle_event(
                                         self.clear_completion(cx.editor);
 
                                         // In case the popup was deleted because of an intersection w/ the auto-complete menu.
+                                        //
+                                        // This also happens to be what covers "show signature help after accepting a
+                                        // function completion": `signature_help_impl` re-requests help at the cursor's
+                                        // new position regardless of why it was called, so if the accepted item inserted
+                                        // `(`, the server's response naturally has a signature to show. There's no need
+                                        // to special-case "does the inserted text end with an open paren" here - the
+                                        // request just comes back empty otherwise and nothing is shown.
+                                        //
+                                        // What's *not* covered is a `CompletionItem::command` asking the client to run
+                                        // an arbitrary follow-up command (the spec's other way to trigger this): unlike
+                                        // code actions (`lsp::apply_code_action`), accepting a completion here never
+                                        // executes `item.command` at all, so a server that relies solely on that to
+                                        // request signature help wouldn't get it from this call.
                                         commands::signature_help_impl(
                                             &mut cx,
                                             commands::SignatureHelpInvoked::Automatic,
                                         );
+
+                                        // Same reasoning applies to retriggering completion: on
+                                        // abort the savepoint restore above already reverted the
+                                        // inserted text, so the trigger-char check below simply
+                                        // won't match.
+                                        commands::insert::retrigger_completion_after_accept(
+                                            &mut cx,
+                                        );
                                     }
                                 }
                             }
@@ -197,6 +197,13 @@ fn adjust_scroll(&mut self) {
         }
     }
 
+    /// The index into the *filtered* matches the selection currently sits at, or `None` if
+    /// nothing is selected. Distinct from an index into `options`: it moves as `score`/`clear`
+    /// reset it and is stable across re-filtering only insofar as the callback re-selects it.
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
     pub fn selection(&self) -> Option<&T> {
         self.cursor.and_then(|cursor| {
             self.matches
@@ -265,6 +272,13 @@ fn handle_event(&mut self, event: &Event, cx: &mut Context) -> EventResult {
                 (self.callback_fn)(cx.editor, self.selection(), MenuEvent::Update);
                 return EventResult::Consumed(None);
             }
+            // Enter accepts the current selection, or - if nothing is selected - is ignored so
+            // it falls through to the normal insert-mode handler and inserts a newline instead.
+            // `Tab` is intentionally not a second, distinct way to accept: it's already bound to
+            // cycling the selection above, matching the arrow keys/ctrl-n/ctrl-p, so repurposing
+            // it here would mean losing keyboard-only cycling instead of adding an accept key.
+            // `replace` mode (replace vs insert the remainder of the word) is controlled by
+            // `editor.completion_replace` (see `Completion::new`), not by which key accepted.
             key!(Enter) => {
                 if let Some(selection) = self.selection() {
                     (self.callback_fn)(cx.editor, Some(selection), MenuEvent::Validate);
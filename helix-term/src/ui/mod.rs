@@ -13,6 +13,7 @@
 mod spinner;
 mod statusline;
 mod text;
+pub mod tree;
 
 use crate::compositor::{Component, Compositor};
 use crate::filter_picker_entry;
@@ -26,6 +27,7 @@
 pub use prompt::{Prompt, PromptEvent};
 pub use spinner::{ProgressSpinners, Spinner};
 pub use text::Text;
+pub use tree::{Tree, TreeData};
 
 use helix_core::regex::Regex;
 use helix_core::regex::RegexBuilder;
@@ -0,0 +1,1040 @@
+//! A generic, lazily-expanded tree widget used for things like a file explorer.
+//!
+//! The tree itself holds no knowledge of what a "file" or "directory" is; instead callers
+//! implement [`TreeData`] which describes how to enumerate the children of a node. Nodes are
+//! only materialized once their ancestor has been expanded, so opening a huge directory doesn't
+//! require walking the whole subtree up front.
+//!
+//! This tree has no filesystem-backed `TreeData` implementation yet (no `DirectoryTree`, no
+//! `walk_path`) — only the test-only in-memory `TestData`/`BadgedData` impls in `tests.rs` — so
+//! there is nowhere yet to special-case case-insensitive filesystems during a walk.
+
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// Describes the backing data for a [`Tree`]. `Node` is an opaque path segment (e.g. a file
+/// name) that the tree stores and hands back to `expand`/`label` to identify a node.
+pub trait TreeData {
+    type Node: Clone + PartialEq + std::fmt::Debug;
+
+    /// Returns the children of the node at `path`. Called once the first time a node is
+    /// expanded (or on `refresh`), and the result is cached on the [`Node`] until invalidated.
+    fn expand(&mut self, path: &[Self::Node]) -> Vec<Self::Node>;
+
+    /// Whether the node at `path` is a leaf that can't be expanded at all (e.g. a regular file).
+    fn is_leaf(&self, path: &[Self::Node]) -> bool;
+
+    /// The text shown for the node at `path`.
+    fn label(&self, path: &[Self::Node]) -> String;
+
+    /// An optional badge shown alongside the node's label, e.g. a modified-file count for a
+    /// collapsed directory or an LSP diagnostic count for a file. Defaults to `None` so data
+    /// sources that don't need badges aren't burdened with implementing this.
+    fn badge(&self, _path: &[Self::Node]) -> Option<String> {
+        None
+    }
+
+    /// An optional glyph shown alongside the node's label, e.g. a file-type icon, or a
+    /// folder-open/folder-closed icon for a directory (`is_leaf` is `false` and `is_expanded`
+    /// tells the two apart). Defaults to `None` so non-file data sources aren't affected.
+    fn icon(&self, _path: &[Self::Node], _is_leaf: bool, _is_expanded: bool) -> Option<&str> {
+        None
+    }
+}
+
+/// Alternative to [`TreeData`] for a data source whose model needs to be shared - e.g. behind an
+/// `Rc`/`Arc` with other parts of the UI, like a global repo/status cache - and so can't be
+/// handed to `Tree` as an owned `&mut`. `expand` takes `&self` instead; any caching it wants to
+/// do (mirroring the "cached on the `Node` until invalidated" note on `TreeData::expand`) has to
+/// go through interior mutability (`RefCell`/`Cell`) rather than a plain field write.
+///
+/// `Tree` itself is only ever generic over [`TreeData`], not over "which of the two traits this
+/// is" - duplicating every method on `Tree` behind a second bound would multiply the size of this
+/// file for what's really only a difference in one method's receiver. Instead, the blanket impl
+/// below makes any `TreeDataRef` usable as a `TreeData` for free (`&self` methods already satisfy
+/// a `&mut self` signature), so `Tree::new(shared_data, ..)` works unchanged as long as
+/// `shared_data: TreeDataRef` - e.g. a `Clone`-able wrapper around `Rc<RefCell<_>>` that several
+/// `Tree`s (or other UI components) hold their own handle to.
+pub trait TreeDataRef {
+    type Node: Clone + PartialEq + std::fmt::Debug;
+
+    /// See [`TreeData::expand`].
+    fn expand(&self, path: &[Self::Node]) -> Vec<Self::Node>;
+
+    /// See [`TreeData::is_leaf`].
+    fn is_leaf(&self, path: &[Self::Node]) -> bool;
+
+    /// See [`TreeData::label`].
+    fn label(&self, path: &[Self::Node]) -> String;
+
+    /// See [`TreeData::badge`].
+    fn badge(&self, _path: &[Self::Node]) -> Option<String> {
+        None
+    }
+
+    /// See [`TreeData::icon`].
+    fn icon(&self, _path: &[Self::Node], _is_leaf: bool, _is_expanded: bool) -> Option<&str> {
+        None
+    }
+}
+
+impl<T: TreeDataRef> TreeData for T {
+    type Node = T::Node;
+
+    fn expand(&mut self, path: &[Self::Node]) -> Vec<Self::Node> {
+        TreeDataRef::expand(&*self, path)
+    }
+
+    fn is_leaf(&self, path: &[Self::Node]) -> bool {
+        TreeDataRef::is_leaf(self, path)
+    }
+
+    fn label(&self, path: &[Self::Node]) -> String {
+        TreeDataRef::label(self, path)
+    }
+
+    fn badge(&self, path: &[Self::Node]) -> Option<String> {
+        TreeDataRef::badge(self, path)
+    }
+
+    fn icon(&self, path: &[Self::Node], is_leaf: bool, is_expanded: bool) -> Option<&str> {
+        TreeDataRef::icon(self, path, is_leaf, is_expanded)
+    }
+}
+
+/// What activating a node (see [`Tree::activate`]) means for the caller to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Activation<N> {
+    /// The activated node is a leaf; the caller should act on the full path to it (e.g. open the
+    /// file). Expanding/collapsing a branch is handled by `activate` itself and has no variant
+    /// here, since the tree doesn't need the caller to do anything further in that case.
+    Leaf(Vec<N>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Node<T: TreeData> {
+    pub(crate) name: T::Node,
+    /// Full path from a root down to (and including) this node, cached at construction time so
+    /// callers can borrow a node's path in `O(1)` instead of the flattened view having to rebuild
+    /// it by walking down from the roots (as `visible_nodes`/`collect_visible` do).
+    path: Vec<T::Node>,
+    is_leaf: bool,
+    expanded: bool,
+    /// Populated once the node has been expanded at least once.
+    children: Vec<Node<T>>,
+    /// Count of navigable (visible when fully unfolded) descendants, i.e. the number of rows
+    /// this node contributes to the flattened view when expanded. Kept in sync by
+    /// `expand`/`collapse` so it never needs a subtree walk to answer "how many rows".
+    visible_descendants: usize,
+    /// True for a node injected via [`Tree::add_virtual_root`] rather than discovered through
+    /// `TreeData::expand` - e.g. one entry of a flat "recent files" group rendered above the
+    /// hierarchical tree. Always a leaf. `Tree::refresh` re-creates virtual nodes from the tree's
+    /// own record of them afterwards instead of discarding them the way a real root that
+    /// `TreeData::expand` stops returning would be.
+    is_virtual: bool,
+}
+
+impl<T: TreeData> Node<T> {
+    fn new(name: T::Node, path: Vec<T::Node>, is_leaf: bool) -> Self {
+        Node {
+            name,
+            path,
+            is_leaf,
+            expanded: false,
+            children: Vec::new(),
+            visible_descendants: 0,
+            is_virtual: false,
+        }
+    }
+
+    fn new_virtual(name: T::Node, path: Vec<T::Node>) -> Self {
+        Node {
+            name,
+            path,
+            is_leaf: true,
+            expanded: false,
+            children: Vec::new(),
+            visible_descendants: 0,
+            is_virtual: true,
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.is_leaf
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    /// See [`Tree::add_virtual_root`].
+    pub fn is_virtual(&self) -> bool {
+        self.is_virtual
+    }
+
+    /// Full path from a root down to (and including) this node.
+    pub fn path(&self) -> &[T::Node] {
+        &self.path
+    }
+}
+
+pub struct Tree<T: TreeData> {
+    data: T,
+    roots: Vec<Node<T>>,
+    /// Names of the virtual entries added via [`Self::add_virtual_root`], kept outside `roots` so
+    /// `refresh` (which otherwise rebuilds `roots` from scratch from `TreeData::expand`) knows to
+    /// re-insert them afterwards. Always rendered before the real roots, in the order they were
+    /// added.
+    virtual_root_names: Vec<T::Node>,
+    selection: usize,
+    top: usize,
+    height: usize,
+    scrolloff: usize,
+    /// Index of the node last revealed via `reveal_path`, cleared on the next navigation so the
+    /// UI can pulse it exactly once.
+    just_revealed: Option<usize>,
+    /// `Tree::expand` warns and sets `last_slow_expand` when `TreeData::expand` takes at least
+    /// this long. See [`Self::set_slow_expand_threshold`].
+    slow_expand_threshold: Duration,
+    /// Path of the node whose most recent `Tree::expand` call took at least
+    /// `slow_expand_threshold`, so the render callback can flag it (e.g. "that took a while") for
+    /// one frame. Since `TreeData::expand` is a plain synchronous call with no way to interrupt or
+    /// poll it, there is no live spinner while it runs - only this after-the-fact marker, cleared
+    /// on the next `Tree::expand` call regardless of whether that one is slow.
+    last_slow_expand: Option<Vec<T::Node>>,
+}
+
+/// Default for [`Tree::set_slow_expand_threshold`]: about the point past which a UI action starts
+/// reading as unresponsive rather than instant.
+const DEFAULT_SLOW_EXPAND_THRESHOLD: Duration = Duration::from_millis(500);
+
+impl<T: TreeData> Tree<T> {
+    pub fn new(data: T, height: usize, scrolloff: usize) -> Self {
+        let mut tree = Tree {
+            data,
+            roots: Vec::new(),
+            virtual_root_names: Vec::new(),
+            selection: 0,
+            top: 0,
+            height,
+            scrolloff,
+            just_revealed: None,
+            slow_expand_threshold: DEFAULT_SLOW_EXPAND_THRESHOLD,
+            last_slow_expand: None,
+        };
+        tree.refresh();
+        tree
+    }
+
+    /// Overrides how long `TreeData::expand` may run before `Tree::expand` logs a warning and
+    /// flags the node via `render`'s `was_slow_to_expand` callback argument. Useful for a
+    /// network-backed `TreeData` where the ambient latency is known to run higher than the
+    /// default threshold.
+    pub fn set_slow_expand_threshold(&mut self, threshold: Duration) {
+        self.slow_expand_threshold = threshold;
+    }
+
+    /// Re-expands the root set from scratch, discarding all cached children. Virtual roots added
+    /// via [`Self::add_virtual_root`] are not backed by `TreeData::expand`, so they can't be
+    /// rediscovered this way - they're re-inserted from `virtual_root_names` instead, ahead of
+    /// the freshly-expanded real roots.
+    pub fn refresh(&mut self) {
+        let root_names = self.data.expand(&[]);
+        let real_roots = root_names.into_iter().map(|name| {
+            let is_leaf = self.data.is_leaf(&[name.clone()]);
+            Node::new(name.clone(), vec![name], is_leaf)
+        });
+        self.roots = self
+            .virtual_root_names
+            .iter()
+            .cloned()
+            .map(|name| Node::new_virtual(name.clone(), vec![name]))
+            .chain(real_roots)
+            .collect();
+        self.selection = self.selection.min(self.visible_len().saturating_sub(1));
+        self.ensure_selection_visible();
+    }
+
+    /// Expands every ancestor of `path` that isn't already expanded and selects the node it
+    /// points at, returning its index in the flattened view. A transient "just revealed" marker
+    /// is set on the node so the render callback can briefly pulse/flash it; the marker is
+    /// cleared by the next selection-moving call (`move_up`/`move_down`/`set_selection`/
+    /// `expand`/`collapse`).
+    ///
+    /// Returns `None` if `path` doesn't resolve to a node at all - either because some prefix of
+    /// it never appears among its parent's children, or because an intermediate node is (still)
+    /// a genuine leaf and can't have the rest of `path` underneath it.
+    pub fn reveal_path(&mut self, path: &[T::Node]) -> Option<usize> {
+        if path.is_empty() {
+            return None;
+        }
+        let mut prefix = Vec::new();
+        let idx = Self::reveal_rec(&mut self.data, &mut self.roots, path, &mut prefix)?;
+        self.selection = idx;
+        self.just_revealed = Some(idx);
+        self.ensure_selection_visible();
+        Some(idx)
+    }
+
+    fn reveal_rec(
+        data: &mut T,
+        nodes: &mut [Node<T>],
+        path: &[T::Node],
+        prefix: &mut Vec<T::Node>,
+    ) -> Option<usize> {
+        let pos = nodes.iter().position(|n| n.name == path[0])?;
+        let offset: usize = nodes[..pos]
+            .iter()
+            .map(|n| 1 + if n.expanded { n.visible_descendants } else { 0 })
+            .sum();
+        if path.len() == 1 {
+            return Some(offset);
+        }
+        let node = &mut nodes[pos];
+        if node.is_leaf && !node.expanded {
+            // `is_leaf` was cached when this node was first discovered; a directory that was
+            // empty back then may have gained contents since, so `expand_node` below would
+            // otherwise be a no-op forever. Re-resolve the cached status before deciding whether
+            // this node can be expanded at all.
+            prefix.push(path[0].clone());
+            node.is_leaf = data.is_leaf(prefix);
+            prefix.pop();
+        }
+        if !node.expanded {
+            Self::expand_node(data, prefix, node);
+        }
+        if node.is_leaf {
+            // still a genuine leaf: `path` requires descending further, which doesn't exist.
+            return None;
+        }
+        prefix.push(path[0].clone());
+        let rest = Self::reveal_rec(data, &mut node.children, &path[1..], prefix);
+        prefix.pop();
+        let rest = rest?;
+        node.visible_descendants = node
+            .children
+            .iter()
+            .map(|c| 1 + if c.expanded { c.visible_descendants } else { 0 })
+            .sum();
+        Some(offset + 1 + rest)
+    }
+
+    /// Walks the data model depth-first looking for nodes whose label contains `query`
+    /// (case-insensitively), expanding collapsed subtrees via `TreeData::expand` as needed so a
+    /// search can find files inside directories that haven't been opened yet. Stops after
+    /// visiting `limit` nodes total, so a query that doesn't narrow things down can't scan an
+    /// entire huge filesystem; this is the only bound, since this tree is synchronous and has no
+    /// notion of cancelling a call part-way through.
+    ///
+    /// Every matching path is revealed (ancestors expanded, node selected) exactly as
+    /// `reveal_path` would do, and the full list of matches is returned for the UI to present.
+    /// Directories visited along the way but not themselves matching are left expanded rather
+    /// than collapsed back - undoing exploratory expansion would need to track which nodes were
+    /// already open before the search, which isn't worth the complexity for a search action.
+    pub fn search_deep(&mut self, query: &str, limit: usize) -> Vec<Vec<T::Node>> {
+        let query = query.to_lowercase();
+        let mut matches = Vec::new();
+        let mut visited = 0;
+        let mut path = Vec::new();
+        Self::search_deep_rec(
+            &mut self.data,
+            &mut self.roots,
+            &mut path,
+            &query,
+            limit,
+            &mut visited,
+            &mut matches,
+        );
+        // Expanding subtrees above only keeps each touched node's own `visible_descendants`
+        // correct; ancestors further up need a fixup pass before `reveal_path`'s offset math
+        // (which relies on `visible_descendants` of preceding siblings) can trust it.
+        Self::recompute_visible_descendants(&mut self.roots);
+        for path in &matches {
+            self.reveal_path(path);
+        }
+        matches
+    }
+
+    fn search_deep_rec(
+        data: &mut T,
+        nodes: &mut [Node<T>],
+        path: &mut Vec<T::Node>,
+        query: &str,
+        limit: usize,
+        visited: &mut usize,
+        matches: &mut Vec<Vec<T::Node>>,
+    ) {
+        for node in nodes.iter_mut() {
+            if *visited >= limit {
+                return;
+            }
+            *visited += 1;
+
+            path.push(node.name.clone());
+            if data.label(path).to_lowercase().contains(query) {
+                matches.push(path.clone());
+            }
+            path.pop();
+
+            if !node.is_leaf {
+                Self::expand_node(data, path, node);
+                path.push(node.name.clone());
+                Self::search_deep_rec(data, &mut node.children, path, query, limit, visited, matches);
+                path.pop();
+            }
+        }
+    }
+
+    fn recompute_visible_descendants(nodes: &mut [Node<T>]) -> usize {
+        let mut total = 0;
+        for node in nodes {
+            if node.expanded {
+                node.visible_descendants = Self::recompute_visible_descendants(&mut node.children);
+            }
+            total += 1 + if node.expanded { node.visible_descendants } else { 0 };
+        }
+        total
+    }
+
+    /// Adds a new top-level root (e.g. another workspace folder) without rebuilding the
+    /// existing roots or losing their expansion state.
+    pub fn add_root(&mut self, name: T::Node) {
+        let is_leaf = self.data.is_leaf(std::slice::from_ref(&name));
+        self.roots
+            .push(Node::new(name.clone(), vec![name], is_leaf));
+    }
+
+    /// Injects a virtual, flat top-level entry not backed by `TreeData::expand` - e.g. one file of
+    /// a "recent files" group rendered above the hierarchical tree. Always added as a leaf, ahead
+    /// of every real root but after every virtual root added before it, so repeated calls build up
+    /// a virtual group in the order given. Survives `refresh` (see [`Node::is_virtual`]).
+    pub fn add_virtual_root(&mut self, name: T::Node) {
+        self.virtual_root_names.push(name.clone());
+        let insert_idx = self.virtual_root_names.len() - 1;
+        self.roots
+            .insert(insert_idx, Node::new_virtual(name.clone(), vec![name]));
+        if self.selection >= insert_idx {
+            self.selection += 1;
+        }
+        self.ensure_selection_visible();
+    }
+
+    /// Removes the root at `root_idx`, adjusting the selection if it pointed past it. Note that
+    /// this does not forget a virtual root added via `add_virtual_root` - it comes back on the
+    /// next `refresh` since `virtual_root_names` isn't updated - so removing one this way should
+    /// be avoided until there's a real caller that needs to drop a virtual entry permanently.
+    pub fn remove_root(&mut self, root_idx: usize) {
+        if root_idx >= self.roots.len() {
+            return;
+        }
+        let removed_rows = 1 + if self.roots[root_idx].expanded {
+            self.roots[root_idx].visible_descendants
+        } else {
+            0
+        };
+        let start: usize = self.roots[..root_idx]
+            .iter()
+            .map(|n| 1 + if n.expanded { n.visible_descendants } else { 0 })
+            .sum();
+        self.roots.remove(root_idx);
+        if self.selection >= start + removed_rows {
+            self.selection -= removed_rows;
+        } else if self.selection >= start {
+            self.selection = start.min(self.visible_len().saturating_sub(1));
+        }
+        self.ensure_selection_visible();
+    }
+
+    /// Re-expands just the root at `root_idx`, independent of the other root groups. This is the
+    /// per-root analog of `refresh`.
+    pub fn refresh_root(&mut self, root_idx: usize) {
+        if root_idx >= self.roots.len() {
+            return;
+        }
+        let selected_path = self.selected_path().map(<[T::Node]>::to_vec);
+        let root = &mut self.roots[root_idx];
+        root.expanded = false;
+        root.children.clear();
+        root.visible_descendants = 0;
+        let mut path = Vec::new();
+        Self::expand_node(&mut self.data, &mut path, root);
+        self.selection = self.selection.min(self.visible_len().saturating_sub(1));
+        // `refresh_root` re-fetches every child from scratch, so a plain index clamp above can
+        // land the selection on an unrelated node if earlier siblings changed count; snap back
+        // onto the same file if it's still there.
+        if let Some(selected_path) = selected_path {
+            self.reselect_path(&selected_path);
+        }
+        self.ensure_selection_visible();
+    }
+
+    /// Re-runs `TreeData::expand` for an already-expanded `node` and reconciles the result
+    /// against its current `children`: a returned name matching an existing child keeps that
+    /// child (and its own cached subtree/expansion state) as-is, while a name with no match is
+    /// added fresh (collapsed, exactly like a node `expand_node` has never seen), and any
+    /// existing child no longer returned is dropped along with its subtree. This is what lets
+    /// `refresh_subtree` update only what actually changed instead of collapsing the whole
+    /// subtree like `refresh_root` does.
+    fn reconcile_children(data: &mut T, path: &mut Vec<T::Node>, node: &mut Node<T>) {
+        let new_names = data.expand(path);
+        let mut children = Vec::with_capacity(new_names.len());
+        for name in new_names {
+            let child = match node.children.iter().position(|c| c.name == name) {
+                Some(pos) => node.children.remove(pos),
+                None => {
+                    path.push(name.clone());
+                    let is_leaf = data.is_leaf(path);
+                    let child_path = path.clone();
+                    path.pop();
+                    Node::new(name, child_path, is_leaf)
+                }
+            };
+            children.push(child);
+        }
+        node.children = children;
+        node.visible_descendants = Self::recompute_visible_descendants(&mut node.children);
+    }
+
+    /// Walks to the node at `path` by name (unlike `node_at_mut`, which walks by flattened
+    /// index) and, if it's expanded, reconciles its children via `reconcile_children`. Returns
+    /// the node's own flattened index together with how much `visible_descendants` changed, so
+    /// the caller can fix up ancestors and the selection the same way `expand`/`collapse` do.
+    fn refresh_subtree_rec(
+        data: &mut T,
+        nodes: &mut [Node<T>],
+        path: &[T::Node],
+        prefix: &mut Vec<T::Node>,
+    ) -> Option<(usize, isize)> {
+        let pos = nodes.iter().position(|n| n.name == path[0])?;
+        let offset: usize = nodes[..pos]
+            .iter()
+            .map(|n| 1 + if n.expanded { n.visible_descendants } else { 0 })
+            .sum();
+        let node = &mut nodes[pos];
+        if path.len() == 1 {
+            if node.is_leaf || !node.expanded {
+                return Some((offset, 0));
+            }
+            let before = node.visible_descendants;
+            prefix.push(path[0].clone());
+            Self::reconcile_children(data, prefix, node);
+            prefix.pop();
+            return Some((offset, node.visible_descendants as isize - before as isize));
+        }
+        prefix.push(path[0].clone());
+        let rest = Self::refresh_subtree_rec(data, &mut node.children, &path[1..], prefix);
+        prefix.pop();
+        let (child_offset, delta) = rest?;
+        if delta != 0 {
+            node.visible_descendants = (node.visible_descendants as isize + delta) as usize;
+        }
+        Some((offset + 1 + child_offset, delta))
+    }
+
+    /// Re-expands just the node at `path`, reconciling its children against the latest
+    /// `TreeData::expand` result while preserving the expansion state (and cached children) of
+    /// surviving descendants. This is the per-node analog of `refresh_root`, for reacting to an
+    /// external change (e.g. a filesystem watcher) to one directory without losing sibling state
+    /// elsewhere in the tree. Does nothing if `path` doesn't resolve to a currently-expanded
+    /// branch - a collapsed node has no cached children to go stale, so it will simply expand
+    /// fresh the next time it's opened.
+    pub fn refresh_subtree(&mut self, path: &[T::Node]) {
+        if path.is_empty() {
+            self.refresh();
+            return;
+        }
+        let selected_path = self.selected_path().map(<[T::Node]>::to_vec);
+        let mut prefix = Vec::new();
+        let Some((idx, delta)) =
+            Self::refresh_subtree_rec(&mut self.data, &mut self.roots, path, &mut prefix)
+        else {
+            return;
+        };
+        if self.selection > idx {
+            self.selection = (self.selection as isize + delta).max(idx as isize) as usize;
+        }
+        self.selection = self.selection.min(self.visible_len().saturating_sub(1));
+        // The delta shift above only accounts for the reconciled subtree growing or shrinking;
+        // `reconcile_children` can also reorder surviving children, which the shift can't detect.
+        // Snap back onto the exact node the user had selected if it's still there.
+        if let Some(selected_path) = selected_path {
+            self.reselect_path(&selected_path);
+        }
+        self.ensure_selection_visible();
+    }
+
+    fn expand_node(data: &mut T, path: &mut Vec<T::Node>, node: &mut Node<T>) {
+        if node.is_leaf || node.expanded {
+            return;
+        }
+        path.push(node.name.clone());
+        let children = data.expand(path);
+        node.children = children
+            .into_iter()
+            .map(|name| {
+                path.push(name.clone());
+                let is_leaf = data.is_leaf(path);
+                let child_path = path.clone();
+                path.pop();
+                Node::new(name, child_path, is_leaf)
+            })
+            .collect();
+        path.pop();
+        node.expanded = true;
+        node.visible_descendants = node.children.len();
+    }
+
+    /// Expands the node at `idx` in the flattened, currently-visible list. Logs a warning and
+    /// marks the node as `was_slow_to_expand` in the next `render` call if `TreeData::expand`
+    /// takes at least `slow_expand_threshold`; see [`Self::set_slow_expand_threshold`].
+    pub fn expand(&mut self, idx: usize) {
+        let mut path = Vec::new();
+        let delta = {
+            let Some(node) = Self::node_at_mut(&mut self.roots, idx, &mut path) else {
+                return;
+            };
+            let before = node.visible_descendants;
+            let start = Instant::now();
+            Self::expand_node(&mut self.data, &mut path, node);
+            let elapsed = start.elapsed();
+            self.last_slow_expand = (elapsed >= self.slow_expand_threshold).then(|| {
+                let mut full_path = path.clone();
+                full_path.push(node.name.clone());
+                log::warn!(
+                    "expanding {full_path:?} took {elapsed:?}, exceeding the configured {:?} \
+                     slow-expand threshold",
+                    self.slow_expand_threshold
+                );
+                full_path
+            });
+            node.visible_descendants as isize - before as isize
+        };
+        Self::propagate_delta(&mut self.roots, idx, delta);
+        self.just_revealed = None;
+    }
+
+    pub fn collapse(&mut self, idx: usize) {
+        let mut path = Vec::new();
+        let delta = {
+            let Some(node) = Self::node_at_mut(&mut self.roots, idx, &mut path) else {
+                return;
+            };
+            if !node.expanded {
+                return;
+            }
+            let before = node.visible_descendants;
+            node.expanded = false;
+            node.visible_descendants = 0;
+            -(before as isize)
+        };
+        Self::propagate_delta(&mut self.roots, idx, delta);
+        if self.selection > idx {
+            self.selection = (self.selection as isize + delta).max(idx as isize) as usize;
+        }
+        self.just_revealed = None;
+    }
+
+    /// Activates the node at `idx` (e.g. on Enter or a double-click): a leaf is handed back to
+    /// the caller via [`Activation::Leaf`] to do something with (open it as a file), while a
+    /// branch is expanded or collapsed in place and `None` is returned, since the tree has
+    /// already handled it. This centralizes the `is_leaf` check here instead of every consumer
+    /// calling into `TreeData` to decide between opening and toggling for itself.
+    pub fn activate(&mut self, idx: usize) -> Option<Activation<T::Node>> {
+        let mut path = Vec::new();
+        let (is_leaf, expanded) = {
+            let node = Self::node_at_mut(&mut self.roots, idx, &mut path)?;
+            path.push(node.name.clone());
+            (node.is_leaf(), node.expanded)
+        };
+        if is_leaf {
+            return Some(Activation::Leaf(path));
+        }
+        if expanded {
+            self.collapse(idx);
+        } else {
+            self.expand(idx);
+        }
+        None
+    }
+
+    /// Moves the selection to the parent of the currently selected node, or does nothing if it
+    /// is already a root.
+    pub fn move_to_parent(&mut self) {
+        let nodes = self.visible_nodes();
+        let Some((path, _)) = nodes.get(self.selection) else {
+            return;
+        };
+        let depth = path.len();
+        if depth <= 1 {
+            return;
+        }
+        // Pre-order flattening guarantees the nearest preceding entry one level shallower is
+        // this node's parent.
+        if let Some(parent_idx) = nodes[..self.selection]
+            .iter()
+            .rposition(|(p, _)| p.len() == depth - 1)
+        {
+            self.set_selection(parent_idx);
+        }
+    }
+
+    /// Moves the selection to the first child of the currently selected node, expanding it first
+    /// if necessary. Does nothing if the node is a leaf.
+    pub fn move_to_first_child(&mut self) {
+        let is_leaf = self
+            .visible_nodes()
+            .get(self.selection)
+            .map_or(true, |(_, node)| node.is_leaf());
+        if !is_leaf {
+            self.expand(self.selection);
+            self.set_selection(self.selection + 1);
+        }
+    }
+
+    /// Walks the flattened list, looking up the node at `idx` and recording the path to it.
+    fn node_at_mut<'a>(
+        nodes: &'a mut [Node<T>],
+        mut idx: usize,
+        path: &mut Vec<T::Node>,
+    ) -> Option<&'a mut Node<T>> {
+        for node in nodes {
+            if idx == 0 {
+                return Some(node);
+            }
+            idx -= 1;
+            if node.expanded {
+                if idx < node.visible_descendants {
+                    path.push(node.name.clone());
+                    return Self::node_at_mut(&mut node.children, idx, path);
+                }
+                idx -= node.visible_descendants;
+            }
+        }
+        None
+    }
+
+    /// Adjusts `visible_descendants` on every ancestor of the node at `idx` by `delta`, since
+    /// expanding/collapsing a node changes how many rows all its ancestors contribute.
+    fn propagate_delta(nodes: &mut [Node<T>], mut idx: usize, delta: isize) {
+        for node in nodes {
+            if idx == 0 {
+                return;
+            }
+            idx -= 1;
+            if node.expanded {
+                if idx < node.visible_descendants {
+                    node.visible_descendants = (node.visible_descendants as isize + delta) as usize;
+                    Self::propagate_delta(&mut node.children, idx, delta);
+                    return;
+                }
+                idx -= node.visible_descendants;
+            }
+        }
+    }
+
+    /// Total number of navigable (currently visible) rows. `O(1)`: derived from the roots'
+    /// cached `visible_descendants`, which `expand`/`collapse`/`refresh` already keep up to date,
+    /// rather than walking the flattened tree. Combined with `selection()`, a caller can render a
+    /// scrollbar position as `selection() / visible_len()` without an `O(n)` walk of its own.
+    pub fn visible_len(&self) -> usize {
+        self.roots.len()
+            + self
+                .roots
+                .iter()
+                .map(|n| if n.expanded { n.visible_descendants } else { 0 })
+                .sum::<usize>()
+    }
+
+    pub fn selection(&self) -> usize {
+        self.selection
+    }
+
+    /// Path of the currently selected node - e.g. for rendering a breadcrumb header, or a
+    /// "copy path" command, without the caller tracking paths itself. Also used internally to
+    /// survive a structural change (`refresh_root`, `refresh_subtree`) by re-resolving
+    /// the selection from its path afterwards instead of trusting the index to still point at the
+    /// same node. `None` if the tree is empty.
+    pub fn selected_path(&self) -> Option<&[T::Node]> {
+        Self::node_at(&self.roots, self.selection).map(Node::path)
+    }
+
+    /// Read-only counterpart to `node_at_mut`: looks up the node at `idx` in the flattened,
+    /// currently-visible list without needing a `path` accumulator, since each `Node` already
+    /// caches its own path.
+    fn node_at(nodes: &[Node<T>], mut idx: usize) -> Option<&Node<T>> {
+        for node in nodes {
+            if idx == 0 {
+                return Some(node);
+            }
+            idx -= 1;
+            if node.expanded {
+                if idx < node.visible_descendants {
+                    return Self::node_at(&node.children, idx);
+                }
+                idx -= node.visible_descendants;
+            }
+        }
+        None
+    }
+
+    /// Read-only counterpart to `reveal_path`: looks up `path` in the flattened view without
+    /// expanding anything, returning its index only if every ancestor is already expanded (i.e.
+    /// the node is currently visible). Used to snap the selection back onto a specific node after
+    /// a structural change reshuffled indices, without the side effect of opening new branches
+    /// that `reveal_path` has.
+    fn resolve_path(&self, path: &[T::Node]) -> Option<usize> {
+        if path.is_empty() {
+            return None;
+        }
+        Self::resolve_path_rec(&self.roots, path)
+    }
+
+    fn resolve_path_rec(nodes: &[Node<T>], path: &[T::Node]) -> Option<usize> {
+        let pos = nodes.iter().position(|n| n.name == path[0])?;
+        let offset: usize = nodes[..pos]
+            .iter()
+            .map(|n| 1 + if n.expanded { n.visible_descendants } else { 0 })
+            .sum();
+        if path.len() == 1 {
+            return Some(offset);
+        }
+        let node = &nodes[pos];
+        if !node.expanded {
+            return None;
+        }
+        Self::resolve_path_rec(&node.children, &path[1..]).map(|rest| offset + 1 + rest)
+    }
+
+    /// Re-resolves `path` to an index and selects it if it's still visible, leaving the selection
+    /// untouched otherwise (e.g. the node was removed or one of its ancestors collapsed).
+    /// Returns whether the path was found.
+    fn reselect_path(&mut self, path: &[T::Node]) -> bool {
+        match self.resolve_path(path) {
+            Some(idx) => {
+                self.selection = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_selection(&mut self, idx: usize) {
+        self.selection = idx.min(self.visible_len().saturating_sub(1));
+        self.just_revealed = None;
+        self.ensure_selection_visible();
+    }
+
+    pub fn move_down(&mut self) {
+        self.set_selection(self.selection + 1);
+    }
+
+    pub fn move_up(&mut self) {
+        self.set_selection(self.selection.saturating_sub(1));
+    }
+
+    /// Selects the first navigable (visible) node.
+    pub fn move_to_first(&mut self) {
+        self.set_selection(0);
+    }
+
+    /// Selects the last navigable (visible) node, i.e. the last node reachable by repeated
+    /// `move_down` from the first, accounting for which nodes are currently expanded.
+    pub fn move_to_last(&mut self) {
+        self.set_selection(self.visible_len().saturating_sub(1));
+    }
+
+    /// A resize never touches `selection` itself - it only repositions the viewport - so the
+    /// selected node's identity is already stable across it. It's a `refresh_root`/
+    /// `refresh_subtree` call *after* the resize (reacting to whatever prompted it) that can move
+    /// the index; those re-resolve the selection by path for exactly that reason.
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+        self.ensure_selection_visible();
+    }
+
+    /// Updates the scrolloff and repositions the viewport immediately, so a config reload or
+    /// resize takes effect without waiting for the next selection change. `ensure_selection_visible`
+    /// already clamps it to `height / 2`, so no clamping is needed here.
+    pub fn set_scrolloff(&mut self, scrolloff: usize) {
+        self.scrolloff = scrolloff;
+        self.ensure_selection_visible();
+    }
+
+    pub fn ensure_selection_visible(&mut self) {
+        let scrolloff = self.scrolloff.min(self.height / 2);
+        if self.selection < self.top + scrolloff {
+            self.top = self.selection.saturating_sub(scrolloff);
+        } else if self.height > 0 && self.selection + scrolloff >= self.top + self.height {
+            self.top = self.selection + scrolloff + 1 - self.height;
+        }
+    }
+
+    /// Flattens the currently visible nodes (respecting `expanded`) into `(path, &Node)` pairs
+    /// for rendering, in display order.
+    pub fn visible_nodes(&self) -> Vec<(Vec<T::Node>, &Node<T>)> {
+        let mut out = Vec::with_capacity(self.visible_len());
+        let mut path = Vec::new();
+        Self::collect_visible(&self.roots, &mut path, &mut out);
+        out
+    }
+
+    fn collect_visible<'a>(
+        nodes: &'a [Node<T>],
+        path: &mut Vec<T::Node>,
+        out: &mut Vec<(Vec<T::Node>, &'a Node<T>)>,
+    ) {
+        for node in nodes {
+            path.push(node.name.clone());
+            out.push((path.clone(), node));
+            if node.expanded {
+                Self::collect_visible(&node.children, path, out);
+            }
+            path.pop();
+        }
+    }
+
+    /// Calls `f` once per currently-visible node in display order with its path, whether it is
+    /// the current selection, its badge (if `TreeData::badge` returns one for it), its icon (if
+    /// `TreeData::icon` returns one for it), whether it was just revealed via `reveal_path` (for
+    /// a one-shot highlight pulse), and whether the last time it was expanded took at least
+    /// `slow_expand_threshold` (for a one-shot "that took a while" note; see
+    /// [`Self::set_slow_expand_threshold`]).
+    pub fn render(&self, mut f: impl FnMut(&[T::Node], bool, Option<&str>, Option<&str>, bool, bool)) {
+        for (idx, (path, node)) in self.visible_nodes().into_iter().enumerate() {
+            let badge = self.data.badge(&path);
+            let icon = self.data.icon(&path, node.is_leaf(), node.is_expanded());
+            f(
+                &path,
+                idx == self.selection,
+                badge.as_deref(),
+                icon,
+                self.just_revealed == Some(idx),
+                self.last_slow_expand.as_deref() == Some(path.as_slice()),
+            );
+        }
+    }
+
+    /// Renders an indented textual representation of the tree for tests/snapshots: one line per
+    /// visible node showing its depth, expansion state, and whether it is the current selection
+    /// or within the scrolled viewport window (`top..top + height`). This is far more meaningful
+    /// for assertions than `format!("{:?}", tree)`, which only shows a flat set of paths.
+    pub fn render_to_string(&self) -> String {
+        let mut out = String::new();
+        for (idx, (path, node)) in self.visible_nodes().into_iter().enumerate() {
+            let in_viewport = idx >= self.top && idx < self.top + self.height;
+            let marker = if idx == self.selection {
+                '>'
+            } else if in_viewport {
+                ' '
+            } else {
+                '.'
+            };
+            let depth = path.len().saturating_sub(1);
+            let expansion = if node.is_leaf() {
+                ""
+            } else if node.is_expanded() {
+                "v "
+            } else {
+                "> "
+            };
+            out.push(marker);
+            out.push(' ');
+            out.push_str(&"  ".repeat(depth));
+            out.push_str(expansion);
+            out.push_str(&self.data.label(&path));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
+
+/// Scoped to `T::Node: AsRef<str>` rather than widening [`TreeData::Node`]'s own bound, since
+/// only [`Tree::narrow_to_path_prefix`] below needs to compare a partially-typed component
+/// against a candidate's name as text - every other method works with `T::Node` purely by
+/// equality.
+impl<T: TreeData> Tree<T>
+where
+    T::Node: AsRef<str>,
+{
+    /// Incremental "go to path" navigation for a component typed one piece at a time (e.g. a
+    /// path being typed into a prompt): expands each of `components` that matches an existing
+    /// child exactly, except the last one, which is allowed to be a partial, in-progress
+    /// component - it's matched exactly first, and if nothing matches exactly, the first child
+    /// whose name starts with it is selected instead. This builds on [`Self::reveal_path`], which
+    /// only ever matches exactly and so can't select a node the caller hasn't finished typing.
+    ///
+    /// Returns the index of the node ultimately selected in the flattened view, or `None` if
+    /// `components` is empty or some component (other than a partial match on the last one)
+    /// doesn't resolve to anything.
+    pub fn narrow_to_path_prefix(&mut self, components: &[T::Node]) -> Option<usize> {
+        if components.is_empty() {
+            return None;
+        }
+        let mut prefix = Vec::new();
+        let idx = Self::narrow_rec(&mut self.data, &mut self.roots, components, &mut prefix)?;
+        self.selection = idx;
+        self.just_revealed = Some(idx);
+        self.ensure_selection_visible();
+        Some(idx)
+    }
+
+    fn narrow_rec(
+        data: &mut T,
+        nodes: &mut [Node<T>],
+        components: &[T::Node],
+        prefix: &mut Vec<T::Node>,
+    ) -> Option<usize> {
+        let is_last = components.len() == 1;
+        let pos = if is_last {
+            nodes.iter().position(|n| n.name == components[0]).or_else(|| {
+                nodes
+                    .iter()
+                    .position(|n| n.name.as_ref().starts_with(components[0].as_ref()))
+            })?
+        } else {
+            nodes.iter().position(|n| n.name == components[0])?
+        };
+        let offset: usize = nodes[..pos]
+            .iter()
+            .map(|n| 1 + if n.expanded { n.visible_descendants } else { 0 })
+            .sum();
+        if is_last {
+            return Some(offset);
+        }
+
+        let node = &mut nodes[pos];
+        if node.is_leaf && !node.expanded {
+            // See the identical re-resolution in `reveal_rec`: a cached leaf may have gained
+            // children since it was first discovered.
+            prefix.push(node.name.clone());
+            node.is_leaf = data.is_leaf(prefix);
+            prefix.pop();
+        }
+        if !node.expanded {
+            Self::expand_node(data, prefix, node);
+        }
+        if node.is_leaf {
+            return None;
+        }
+        prefix.push(node.name.clone());
+        let rest = Self::narrow_rec(data, &mut node.children, &components[1..], prefix);
+        prefix.pop();
+        let rest = rest?;
+        node.visible_descendants = node
+            .children
+            .iter()
+            .map(|c| 1 + if c.expanded { c.visible_descendants } else { 0 })
+            .sum();
+        Some(offset + 1 + rest)
+    }
+}
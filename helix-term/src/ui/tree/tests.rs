@@ -0,0 +1,731 @@
+use super::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// An in-memory [`TreeData`] used to exercise [`Tree`] without touching the filesystem.
+/// `fs` maps a joined path (components separated by `/`) to its children; a path with no entry
+/// is treated as a leaf.
+#[derive(Default)]
+pub(super) struct TestData {
+    pub(super) fs: HashMap<String, Vec<&'static str>>,
+}
+
+impl TestData {
+    fn key(path: &[String]) -> String {
+        path.join("/")
+    }
+
+    pub(super) fn new(fs: HashMap<String, Vec<&'static str>>) -> Self {
+        TestData { fs }
+    }
+}
+
+impl TreeData for TestData {
+    type Node = String;
+
+    fn expand(&mut self, path: &[String]) -> Vec<String> {
+        self.fs
+            .get(&Self::key(path))
+            .map(|children| children.iter().map(|c| c.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    fn is_leaf(&self, path: &[String]) -> bool {
+        !self.fs.contains_key(&Self::key(path))
+    }
+
+    fn label(&self, path: &[String]) -> String {
+        path.last().cloned().unwrap_or_default()
+    }
+}
+
+fn sample_tree() -> Tree<TestData> {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    Tree::new(TestData::new(fs), 10, 0)
+}
+
+#[test]
+fn refresh_populates_roots() {
+    let tree = sample_tree();
+    assert_eq!(tree.visible_len(), 2);
+    assert_eq!(tree.render_to_string(), "> > foo\n  bar.txt\n");
+}
+
+#[test]
+fn expand_reveals_children_and_collapse_hides_them() {
+    let mut tree = sample_tree();
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+    assert_eq!(
+        tree.render_to_string(),
+        "> v foo\n    baz.txt\n    qux.txt\n  bar.txt\n"
+    );
+
+    tree.collapse(0);
+    assert_eq!(tree.visible_len(), 2);
+    assert_eq!(tree.render_to_string(), "> > foo\n  bar.txt\n");
+}
+
+#[test]
+fn multi_root_add_and_remove() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["workspace-a", "workspace-b"]);
+    fs.insert("workspace-a".to_string(), vec!["a.txt"]);
+    fs.insert("workspace-b".to_string(), vec!["b.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+    assert_eq!(tree.visible_len(), 2);
+
+    tree.data_mut()
+        .fs
+        .insert("bookmarks".to_string(), vec!["favorite.txt"]);
+    tree.add_root("bookmarks".to_string());
+    assert_eq!(tree.visible_len(), 3);
+
+    tree.refresh_root(2);
+    tree.expand(2);
+    assert_eq!(tree.visible_len(), 4);
+
+    tree.remove_root(0);
+    assert_eq!(tree.visible_len(), 3);
+}
+
+#[test]
+fn virtual_roots_render_above_real_ones_and_survive_refresh() {
+    let mut tree = sample_tree();
+    tree.add_virtual_root("recent-1.txt".to_string());
+    tree.add_virtual_root("recent-2.txt".to_string());
+
+    // Virtual entries render first, in the order they were added, ahead of the real roots.
+    assert_eq!(tree.visible_len(), 4);
+    assert_eq!(
+        tree.render_to_string(),
+        "  recent-1.txt\n  recent-2.txt\n> > foo\n  bar.txt\n"
+    );
+    let virtual_flags: Vec<bool> = tree
+        .visible_nodes()
+        .into_iter()
+        .map(|(_, node)| node.is_virtual())
+        .collect();
+    assert_eq!(virtual_flags, vec![true, true, false, false]);
+
+    // A virtual entry is a leaf: activating it hands the path straight back rather than trying
+    // to expand it.
+    assert_eq!(
+        tree.activate(0),
+        Some(Activation::Leaf(vec!["recent-1.txt".to_string()]))
+    );
+
+    // `TreeData::expand` never mentions the virtual entries, so a real `refresh` would normally
+    // wipe anything it didn't return - virtual roots must survive it regardless.
+    tree.refresh();
+    assert_eq!(tree.visible_len(), 4);
+    assert_eq!(
+        tree.render_to_string(),
+        "  recent-1.txt\n  recent-2.txt\n> > foo\n  bar.txt\n"
+    );
+}
+
+#[test]
+fn reveal_path_expands_ancestors_and_pulses_once() {
+    let mut tree = sample_tree();
+    let idx = tree
+        .reveal_path(&["foo".to_string(), "baz.txt".to_string()])
+        .unwrap();
+    assert_eq!(tree.selection(), idx);
+    assert_eq!(tree.visible_len(), 4);
+
+    let mut pulsed = Vec::new();
+    tree.render(|path, _selected, _badge, _icon, just_revealed, _was_slow| {
+        if just_revealed {
+            pulsed.push(path.to_vec());
+        }
+    });
+    assert_eq!(pulsed, vec![vec!["foo".to_string(), "baz.txt".to_string()]]);
+
+    tree.move_down();
+    let mut pulsed_after = Vec::new();
+    tree.render(|path, _selected, _badge, _icon, just_revealed, _was_slow| {
+        if just_revealed {
+            pulsed_after.push(path.to_vec());
+        }
+    });
+    assert!(pulsed_after.is_empty());
+}
+
+#[test]
+fn narrow_to_path_prefix_expands_exact_components_and_selects_a_partial_last_one() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo"]);
+    fs.insert("foo".to_string(), vec!["bar.txt", "bar2.txt", "baz.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+
+    let idx = tree
+        .narrow_to_path_prefix(&["foo".to_string(), "ba".to_string()])
+        .unwrap();
+    assert_eq!(tree.selection(), idx);
+    // "foo" expanded even though it was only typed exactly, and "ba" (no exact match) selected
+    // the first child starting with it rather than failing to resolve.
+    assert_eq!(
+        tree.render_to_string(),
+        "  v foo\n>   bar.txt\n    bar2.txt\n    baz.txt\n"
+    );
+}
+
+#[test]
+fn narrow_to_path_prefix_prefers_an_exact_match_over_a_prefix_match() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo"]);
+    fs.insert("foo".to_string(), vec!["bar.txt", "bar2.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+
+    tree.narrow_to_path_prefix(&["foo".to_string(), "bar2.txt".to_string()])
+        .unwrap();
+    assert_eq!(
+        tree.render_to_string(),
+        "  v foo\n    bar.txt\n>   bar2.txt\n"
+    );
+}
+
+#[test]
+fn narrow_to_path_prefix_returns_none_when_a_non_final_component_does_not_match() {
+    let mut tree = sample_tree();
+    assert!(tree
+        .narrow_to_path_prefix(&["nope".to_string(), "ba".to_string()])
+        .is_none());
+}
+
+#[test]
+fn move_to_first_and_last_jump_to_the_navigable_ends() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 2, 0);
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+
+    tree.move_to_last();
+    assert_eq!(tree.selection(), 3);
+    assert_eq!(
+        tree.render_to_string(),
+        ". v foo\n.   baz.txt\n    qux.txt\n> bar.txt\n"
+    );
+
+    tree.move_to_first();
+    assert_eq!(tree.selection(), 0);
+    assert_eq!(
+        tree.render_to_string(),
+        "> v foo\n    baz.txt\n.   qux.txt\n. bar.txt\n"
+    );
+}
+
+#[test]
+fn move_to_parent_and_first_child() {
+    let mut tree = sample_tree();
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+
+    tree.set_selection(0);
+    tree.move_to_first_child();
+    assert_eq!(tree.selection(), 1); // foo/baz.txt
+
+    tree.move_to_parent();
+    assert_eq!(tree.selection(), 0); // foo
+
+    // a root has no parent to move to
+    tree.move_to_parent();
+    assert_eq!(tree.selection(), 0);
+
+    // a leaf has no children to move into
+    tree.set_selection(3);
+    tree.move_to_first_child();
+    assert_eq!(tree.selection(), 3);
+}
+
+#[test]
+fn set_scrolloff_repositions_top_immediately() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 2, 0);
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+
+    tree.move_to_last();
+    assert_eq!(
+        tree.render_to_string(),
+        ". v foo\n.   baz.txt\n    qux.txt\n> bar.txt\n"
+    );
+
+    tree.set_scrolloff(1);
+    assert_eq!(
+        tree.render_to_string(),
+        ". v foo\n.   baz.txt\n.   qux.txt\n> bar.txt\n"
+    );
+}
+
+#[test]
+fn search_deep_finds_and_reveals_matches_in_unexpanded_subtrees() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+    // nothing has been expanded yet besides the roots
+    assert_eq!(tree.visible_len(), 2);
+
+    let matches = tree.search_deep("baz", 100);
+    assert_eq!(
+        matches,
+        vec![vec!["foo".to_string(), "baz.txt".to_string()]]
+    );
+    // finding the match required expanding `foo`, which is now reflected in the live tree
+    assert_eq!(tree.visible_len(), 4);
+    assert_eq!(
+        tree.selection(),
+        tree.reveal_path(&["foo".to_string(), "baz.txt".to_string()])
+            .unwrap()
+    );
+}
+
+#[test]
+fn search_deep_stops_after_visiting_limit_nodes() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+
+    // only enough budget to visit `foo` itself, not to descend into its children
+    let matches = tree.search_deep("baz", 1);
+    assert!(matches.is_empty());
+}
+
+#[test]
+fn activate_toggles_branches_and_returns_leaves() {
+    let mut tree = sample_tree();
+
+    // activating the branch at index 0 ("foo") expands it in place and reports nothing to do
+    assert_eq!(tree.activate(0), None);
+    assert_eq!(tree.visible_len(), 4);
+    assert_eq!(tree.activate(0), None);
+    assert_eq!(tree.visible_len(), 2);
+
+    tree.expand(0);
+    assert_eq!(
+        tree.activate(1),
+        Some(Activation::Leaf(vec!["foo".to_string(), "baz.txt".to_string()]))
+    );
+    // activating a leaf doesn't change what's visible
+    assert_eq!(tree.visible_len(), 4);
+}
+
+#[test]
+fn refresh_subtree_reconciles_one_branch_and_leaves_siblings_alone() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar"]);
+    fs.insert("foo".to_string(), vec!["baz.txt", "qux.txt"]);
+    fs.insert("bar".to_string(), vec!["a.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+    tree.expand(0); // foo
+    tree.expand(3); // bar, now at index 3
+    assert_eq!(tree.visible_len(), 5);
+    tree.set_selection(4); // bar/a.txt
+
+    // "foo" gains "new.txt" and loses "qux.txt" externally; "bar" is untouched.
+    tree.data_mut()
+        .fs
+        .insert("foo".to_string(), vec!["baz.txt", "new.txt"]);
+    tree.refresh_subtree(&["foo".to_string()]);
+
+    assert_eq!(tree.visible_len(), 5);
+    assert_eq!(
+        tree.render_to_string(),
+        "  v foo\n    baz.txt\n    new.txt\n  v bar\n>   a.txt\n"
+    );
+    // the selection tracked "bar/a.txt" through the sibling's reconciliation, since the row
+    // count above it didn't change.
+    assert_eq!(tree.selection(), 4);
+}
+
+#[test]
+fn refresh_subtree_preserves_expansion_state_of_surviving_descendants() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo"]);
+    fs.insert("foo".to_string(), vec!["bar", "baz.txt"]);
+    fs.insert("foo/bar".to_string(), vec!["nested.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+    tree.expand(0); // foo
+    tree.expand(1); // foo/bar
+    assert_eq!(tree.visible_len(), 4);
+
+    // "foo" gains a new sibling of "bar"; "bar" itself (and its expanded child) survives as-is.
+    tree.data_mut().fs.insert(
+        "foo".to_string(),
+        vec!["bar", "baz.txt", "new.txt"],
+    );
+    tree.refresh_subtree(&["foo".to_string()]);
+
+    assert_eq!(tree.visible_len(), 5);
+    assert_eq!(
+        tree.render_to_string(),
+        "> v foo\n    v bar\n      nested.txt\n    baz.txt\n    new.txt\n"
+    );
+}
+
+#[test]
+fn refresh_subtree_does_nothing_on_a_collapsed_or_missing_path() {
+    let mut tree = sample_tree();
+
+    // "foo" hasn't been expanded, so there's no cached subtree to go stale.
+    tree.refresh_subtree(&["foo".to_string()]);
+    assert_eq!(tree.visible_len(), 2);
+
+    // a path that doesn't resolve to any node is simply ignored.
+    tree.refresh_subtree(&["does-not-exist".to_string()]);
+    assert_eq!(tree.visible_len(), 2);
+}
+
+#[test]
+fn reveal_path_re_resolves_a_leaf_that_gained_children() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo"]);
+    fs.insert("foo".to_string(), vec!["empty"]);
+    // no entry for "foo/empty": it starts out as a genuine leaf.
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 2);
+
+    // a path that still doesn't exist correctly resolves to nothing.
+    assert_eq!(
+        tree.reveal_path(&[
+            "foo".to_string(),
+            "empty".to_string(),
+            "inner.txt".to_string()
+        ]),
+        None
+    );
+
+    // "foo/empty" gains a child externally, without the tree being told about it directly.
+    tree.data_mut()
+        .fs
+        .insert("foo/empty".to_string(), vec!["inner.txt"]);
+
+    let idx = tree
+        .reveal_path(&[
+            "foo".to_string(),
+            "empty".to_string(),
+            "inner.txt".to_string(),
+        ])
+        .unwrap();
+    assert_eq!(tree.selection(), idx);
+    assert_eq!(
+        tree.render_to_string(),
+        "  v foo\n    v empty\n>     inner.txt\n"
+    );
+}
+
+struct BadgedData(TestData);
+
+impl TreeData for BadgedData {
+    type Node = String;
+
+    fn expand(&mut self, path: &[String]) -> Vec<String> {
+        self.0.expand(path)
+    }
+
+    fn is_leaf(&self, path: &[String]) -> bool {
+        self.0.is_leaf(path)
+    }
+
+    fn label(&self, path: &[String]) -> String {
+        self.0.label(path)
+    }
+
+    fn badge(&self, path: &[String]) -> Option<String> {
+        (path.last().map(String::as_str) == Some("foo")).then(|| "2".to_string())
+    }
+}
+
+#[test]
+fn render_surfaces_badges_per_node() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt"]);
+    let tree = Tree::new(BadgedData(TestData::new(fs)), 10, 0);
+
+    let mut badges = Vec::new();
+    tree.render(|path, _selected, badge, _icon, _just_revealed, _was_slow| {
+        badges.push((path.to_vec(), badge.map(str::to_string)))
+    });
+    assert_eq!(badges[0], (vec!["foo".to_string()], Some("2".to_string())));
+    assert_eq!(badges[1], (vec!["bar.txt".to_string()], None));
+}
+
+struct IconData(TestData);
+
+impl TreeData for IconData {
+    type Node = String;
+
+    fn expand(&mut self, path: &[String]) -> Vec<String> {
+        self.0.expand(path)
+    }
+
+    fn is_leaf(&self, path: &[String]) -> bool {
+        self.0.is_leaf(path)
+    }
+
+    fn label(&self, path: &[String]) -> String {
+        self.0.label(path)
+    }
+
+    fn icon(&self, _path: &[String], is_leaf: bool, is_expanded: bool) -> Option<&str> {
+        Some(match (is_leaf, is_expanded) {
+            (true, _) => "file",
+            (false, true) => "folder-open",
+            (false, false) => "folder-closed",
+        })
+    }
+}
+
+#[test]
+fn render_surfaces_icons_distinguishing_leaves_from_open_and_closed_directories() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt"]);
+    let mut tree = Tree::new(IconData(TestData::new(fs)), 10, 0);
+    tree.reveal_path(&["foo".to_string(), "baz.txt".to_string()])
+        .unwrap();
+
+    let mut icons = Vec::new();
+    tree.render(|path, _selected, _badge, icon, _just_revealed, _was_slow| {
+        icons.push((path.to_vec(), icon.map(str::to_string)))
+    });
+    assert_eq!(
+        icons,
+        vec![
+            (vec!["foo".to_string()], Some("folder-open".to_string())),
+            (
+                vec!["foo".to_string(), "baz.txt".to_string()],
+                Some("file".to_string())
+            ),
+            (vec!["bar.txt".to_string()], Some("file".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn visible_len_tracks_nested_expand_and_collapse() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["nested", "baz.txt"]);
+    fs.insert("foo/nested".to_string(), vec!["a.txt", "b.txt"]);
+    let mut tree = Tree::new(TestData::new(fs), 10, 0);
+
+    // "foo" and "bar.txt" at the root.
+    assert_eq!(tree.visible_len(), 2);
+
+    // expanding "foo" reveals "nested" and "baz.txt".
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+
+    // expanding "nested" reveals its two children on top of the above.
+    tree.expand(1);
+    assert_eq!(tree.visible_len(), 6);
+
+    // collapsing "foo" hides the whole subtree, including the still-expanded "nested".
+    tree.collapse(0);
+    assert_eq!(tree.visible_len(), 2);
+
+    // re-expanding "foo" re-fetches its children from scratch, so "nested" comes back
+    // collapsed even though it was expanded before "foo" was collapsed.
+    tree.expand(0);
+    assert_eq!(tree.visible_len(), 4);
+
+    // `selection()` is a flattened index into the same navigable rows `visible_len()` counts,
+    // so a scrollbar can render position as `selection() / visible_len()` without its own walk.
+    tree.move_to_last();
+    assert_eq!(tree.selection(), tree.visible_len() - 1);
+}
+
+#[test]
+fn resize_and_refresh_keep_the_selected_path() {
+    let mut tree = sample_tree();
+    tree.expand(0);
+    // "foo" is expanded: rows are foo, baz.txt, qux.txt, bar.txt. Select "qux.txt".
+    tree.set_selection(2);
+    assert_eq!(
+        tree.selected_path(),
+        Some(["foo".to_string(), "qux.txt".to_string()].as_slice())
+    );
+
+    // A resize (e.g. the terminal window changing size) never moves the selection on its own.
+    tree.set_height(5);
+    assert_eq!(
+        tree.selected_path(),
+        Some(["foo".to_string(), "qux.txt".to_string()].as_slice())
+    );
+
+    // Something external reorders "foo"'s children (e.g. a directory listing sorted
+    // differently) and a new entry is inserted before "qux.txt". A naive delta-shift would
+    // leave the selection pointing at whatever row 2 happens to be now; refresh_subtree
+    // must instead re-resolve by path so the same file stays selected.
+    tree.data_mut()
+        .fs
+        .insert("foo".to_string(), vec!["aaa.txt", "baz.txt", "qux.txt"]);
+    tree.refresh_subtree(&["foo".to_string()]);
+
+    assert_eq!(
+        tree.selected_path(),
+        Some(["foo".to_string(), "qux.txt".to_string()].as_slice())
+    );
+    assert_eq!(tree.selection(), 3);
+}
+
+#[test]
+fn selected_path_tracks_navigation_for_a_breadcrumb() {
+    let mut tree = sample_tree();
+    assert_eq!(tree.selected_path(), Some(["foo".to_string()].as_slice()));
+
+    tree.expand(0);
+    tree.move_down();
+    assert_eq!(
+        tree.selected_path(),
+        Some(["foo".to_string(), "baz.txt".to_string()].as_slice())
+    );
+
+    tree.move_down();
+    assert_eq!(
+        tree.selected_path(),
+        Some(["foo".to_string(), "qux.txt".to_string()].as_slice())
+    );
+
+    tree.move_to_last();
+    assert_eq!(tree.selected_path(), Some(["bar.txt".to_string()].as_slice()));
+}
+
+/// A [`TreeData`] that sleeps for a configured duration on every `expand`, simulating a
+/// network filesystem slow enough to trip `Tree::set_slow_expand_threshold`.
+struct SlowData {
+    inner: TestData,
+    delay: std::time::Duration,
+}
+
+impl TreeData for SlowData {
+    type Node = String;
+
+    fn expand(&mut self, path: &[String]) -> Vec<String> {
+        std::thread::sleep(self.delay);
+        self.inner.expand(path)
+    }
+
+    fn is_leaf(&self, path: &[String]) -> bool {
+        self.inner.is_leaf(path)
+    }
+
+    fn label(&self, path: &[String]) -> String {
+        self.inner.label(path)
+    }
+}
+
+/// A [`TreeDataRef`] backed by an `Rc<RefCell<_>>` so its data can be shared between several
+/// `Tree`s (or, in a real UI, other components) that each hold their own clone of the handle.
+#[derive(Clone, Default)]
+struct SharedData(Rc<RefCell<HashMap<String, Vec<&'static str>>>>);
+
+impl SharedData {
+    fn new(fs: HashMap<String, Vec<&'static str>>) -> Self {
+        SharedData(Rc::new(RefCell::new(fs)))
+    }
+
+    fn key(path: &[String]) -> String {
+        path.join("/")
+    }
+}
+
+impl TreeDataRef for SharedData {
+    type Node = String;
+
+    fn expand(&self, path: &[String]) -> Vec<String> {
+        self.0
+            .borrow()
+            .get(&Self::key(path))
+            .map(|children| children.iter().map(|c| c.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    fn is_leaf(&self, path: &[String]) -> bool {
+        !self.0.borrow().contains_key(&Self::key(path))
+    }
+
+    fn label(&self, path: &[String]) -> String {
+        path.last().cloned().unwrap_or_default()
+    }
+}
+
+#[test]
+fn shared_tree_data_can_back_two_independent_trees() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt"]);
+    let data = SharedData::new(fs);
+
+    let tree_a = Tree::new(data.clone(), 10, 0);
+    let mut tree_b = Tree::new(data.clone(), 10, 0);
+    assert_eq!(tree_a.visible_len(), 2);
+
+    tree_b.expand(0); // foo
+    assert_eq!(tree_b.visible_len(), 3);
+
+    // Mutating the shared model through one handle - as an external cache invalidation would -
+    // is visible to any `Tree` built over the same `Rc<RefCell<_>>` once it re-fetches.
+    data.0
+        .borrow_mut()
+        .insert("foo".to_string(), vec!["baz.txt", "new.txt"]);
+    tree_b.refresh_subtree(&["foo".to_string()]);
+
+    assert_eq!(tree_b.visible_len(), 4);
+    assert_eq!(
+        tree_b.render_to_string(),
+        "> v foo\n    baz.txt\n    new.txt\n  bar.txt\n"
+    );
+    // `tree_a` never expanded "foo" and never re-fetched, so it's untouched by the mutation -
+    // each `Tree` keeps its own independent expansion state over the shared model.
+    assert_eq!(tree_a.visible_len(), 2);
+}
+
+#[test]
+fn expand_past_the_threshold_warns_and_flags_the_node_for_one_render() {
+    let mut fs = HashMap::new();
+    fs.insert(String::new(), vec!["foo", "bar.txt"]);
+    fs.insert("foo".to_string(), vec!["baz.txt"]);
+    let mut tree = Tree::new(
+        SlowData {
+            inner: TestData::new(fs),
+            delay: std::time::Duration::from_millis(20),
+        },
+        10,
+        0,
+    );
+    tree.set_slow_expand_threshold(std::time::Duration::from_millis(5));
+
+    tree.expand(0);
+
+    let mut flagged = Vec::new();
+    tree.render(|path, _selected, _badge, _icon, _just_revealed, was_slow| {
+        if was_slow {
+            flagged.push(path.to_vec());
+        }
+    });
+    assert_eq!(flagged, vec![vec!["foo".to_string()]]);
+
+    // expanding a second, fast-enough node clears the marker from the first.
+    tree.expand(2);
+    let mut flagged_after = Vec::new();
+    tree.render(|path, _selected, _badge, _icon, _just_revealed, was_slow| {
+        if was_slow {
+            flagged_after.push(path.to_vec());
+        }
+    });
+    assert!(flagged_after.is_empty());
+}
@@ -1,4 +1,5 @@
 use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use helix_core::Rope;
@@ -22,6 +23,17 @@ struct RenderLock {
     pub timeout: Option<Instant>,
 }
 
+/// An update sent to the `DiffWorker`: either a new document revision or a new diff base,
+/// distinguished by `is_base`.
+///
+/// There is only ever one base here - diffing the working copy against two bases at once (e.g.
+/// "ours" and "theirs" for a merge/rebase preview) isn't supported. Doing that for real wouldn't
+/// just mean adding an `UpdateSecondaryBase` variant here: `DiffInner` below holds a single
+/// `diff_base`/`hunks` pair and `DiffWorker::run` interns and diffs against exactly one of them
+/// at a time, so a second base would need its own `InternedRopeLines`, its own hunk list, and a
+/// combined structure describing how a line that changed relative to both bases should render
+/// (which is genuinely a design question, not just more plumbing) - rather than a second field
+/// bolted onto the existing one-base types.
 struct Event {
     text: Rope,
     is_base: bool,
@@ -35,11 +47,19 @@ struct DiffInner {
     hunks: Vec<Hunk>,
 }
 
+/// A handle to a [`DiffWorker`] running on its own tokio task, communicating over an unbounded
+/// channel - there is no fixed capacity here, so there's no `send_blocking` backpressure cost and
+/// nothing dropped silently under load. Debouncing/coalescing a burst of updates is handled
+/// downstream by `EventAccumulator` in `diff::worker` folding every queued update down to "latest
+/// wins" instead of by bounding this channel. This tree has no generic `AsyncHook` trait with a
+/// configurable channel capacity or backpressure policy for `DiffWorker` to implement instead.
 #[derive(Clone, Debug)]
 pub struct DiffHandle {
     channel: UnboundedSender<Event>,
     render_lock: Arc<RwLock<()>>,
     diff: Arc<Mutex<DiffInner>>,
+    diff_finished_notify: Arc<Notify>,
+    is_modified: Arc<AtomicBool>,
     inverted: bool,
 }
 
@@ -55,17 +75,22 @@ fn new_with_handle(
     ) -> (DiffHandle, JoinHandle<()>) {
         let (sender, receiver) = unbounded_channel();
         let diff: Arc<Mutex<DiffInner>> = Arc::default();
+        let diff_finished_notify: Arc<Notify> = Arc::default();
+        let is_modified: Arc<AtomicBool> = Arc::default();
         let worker = DiffWorker {
             channel: receiver,
             diff: diff.clone(),
             new_hunks: Vec::default(),
             redraw_notify: redraw_handle.0,
-            diff_finished_notify: Arc::default(),
+            diff_finished_notify: diff_finished_notify.clone(),
+            is_modified: is_modified.clone(),
         };
         let handle = tokio::spawn(worker.run(diff_base, doc));
         let differ = DiffHandle {
             channel: sender,
             diff,
+            diff_finished_notify,
+            is_modified,
             inverted: false,
             render_lock: redraw_handle.1,
         };
@@ -83,6 +108,28 @@ pub fn load(&self) -> Diff {
         }
     }
 
+    /// Returns a handle that resolves every time the worker finishes applying a new diff (see
+    /// `DiffWorker::apply_hunks`), so a consumer can react exactly when `load` would return
+    /// something new instead of polling it on a timer. This is the `Notify` the worker already
+    /// uses internally to schedule a redraw - there is no `watch`/`mpsc` channel of diff stats to
+    /// hand out instead, since hunks are read back through `load`, not pushed through the
+    /// notification itself. Call `.notified()` on the result to wait for the next diff; cloning
+    /// `Arc<Notify>` lets any number of independent consumers (gutter, statusline, ...) subscribe,
+    /// and simply dropping their clone is enough to unsubscribe without affecting the worker,
+    /// which holds its own clone for as long as it runs.
+    pub fn subscribe(&self) -> Arc<Notify> {
+        self.diff_finished_notify.clone()
+    }
+
+    /// Returns whether the document currently has any hunks against its diff base at all - the
+    /// same condition `load().is_empty()` checks, but without locking `diff` to find out. Updated
+    /// by the same `DiffWorker::apply_hunks` call that publishes new hunks for `load`, so it's
+    /// never more stale than the hunks themselves; a cheap "is this file modified?" dot in the
+    /// statusline can poll this every render without contending with the gutter's `load()` calls.
+    pub fn is_modified(&self) -> bool {
+        self.is_modified.load(Ordering::Relaxed)
+    }
+
     /// Updates the document associated with this redraw handle
     /// This function is only intended to be called from within the rendering loop
     /// if called from elsewhere it may fail to acquire the render lock and panic
@@ -139,6 +186,13 @@ fn update_document_impl(
 /// assert!(x.before.end <= y.before.start);
 /// assert!(x.after.end <= y.after.start);
 /// ```
+///
+/// This is as fine-grained as diffing gets in this tree: `imara_diff::diff` above is run
+/// line-by-line, there is no character-level differ for modified lines, no `Differ` type, and
+/// `helix-core` has no `syntax::overlay` module for a diff to export into - highlighting goes
+/// through `HighlightEvent`s from `helix-core::syntax`, with `syntax::Span` only as the input of
+/// `syntax::merge`. Exporting a char-level diff as overlay `Span`s would mean building all three
+/// of those missing pieces first, not bridging two existing type systems.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Hunk {
     pub before: Range<u32>,
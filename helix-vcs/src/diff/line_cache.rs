@@ -19,6 +19,10 @@
 
 /// A cache that stores the `lines` of a rope as a vector.
 /// It allows safely reusing the allocation of the vec when updating the rope
+///
+/// This tree has no separate `RopeLineCache` type; `InternedRopeLines` is the line cache, and
+/// its backing ropes are already exposed via [`Self::doc`] and [`Self::diff_base`] (cheap clones,
+/// since `Rope` shares its backing storage).
 pub(crate) struct InternedRopeLines {
     diff_base: Rope,
     doc: Rope,
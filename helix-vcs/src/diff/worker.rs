@@ -1,5 +1,6 @@
 use std::mem::swap;
 use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use helix_core::{Rope, RopeSlice};
@@ -25,6 +26,7 @@ pub(super) struct DiffWorker {
     pub new_hunks: Vec<Hunk>,
     pub redraw_notify: Arc<Notify>,
     pub diff_finished_notify: Arc<Notify>,
+    pub is_modified: Arc<AtomicBool>,
 }
 
 impl DiffWorker {
@@ -47,7 +49,9 @@ pub async fn run(mut self, diff_base: Rope, doc: Rope) {
             self.perform_diff(lines);
         }
         self.apply_hunks(interner.diff_base(), interner.doc());
-        while let Some(event) = self.channel.recv().await {
+
+        let mut next_event = self.channel.recv().await;
+        while let Some(event) = next_event.take() {
             let (doc, diff_base) = self.accumulate_events(event).await;
 
             let process_accumulated_events = || {
@@ -70,7 +74,22 @@ pub async fn run(mut self, diff_base: Rope, doc: Rope) {
             #[cfg(not(test))]
             tokio::task::block_in_place(process_accumulated_events);
 
-            self.apply_hunks(interner.diff_base(), interner.doc());
+            // `perform_diff` above can take a while on a large file, long enough for further
+            // edits to have queued up on the channel while we were computing. In that case the
+            // hunks we just produced are already stale, so skip publishing them and go straight
+            // into the next round with the freshest text instead of flashing a stale diff first.
+            // `imara_diff::diff` itself has no deadline/yield points to abort mid-computation,
+            // so this is a best-effort "don't publish stale work", not true cancellation.
+            match self.channel.try_recv() {
+                Ok(event) => {
+                    self.new_hunks.clear();
+                    next_event = Some(event)
+                }
+                Err(_) => {
+                    self.apply_hunks(interner.diff_base(), interner.doc());
+                    next_event = self.channel.recv().await;
+                }
+            }
         }
     }
 
@@ -82,17 +101,63 @@ fn apply_hunks(&mut self, diff_base: Rope, doc: Rope) {
         diff.diff_base = diff_base;
         diff.doc = doc;
         swap(&mut diff.hunks, &mut self.new_hunks);
+        self.is_modified
+            .store(!diff.hunks.is_empty(), Ordering::Relaxed);
         self.diff_finished_notify.notify_waiters();
         self.new_hunks.clear();
     }
 
     fn perform_diff(&mut self, input: &InternedInput<RopeSlice>) {
-        imara_diff::diff(ALGORITHM, input, |before: Range<u32>, after: Range<u32>| {
-            self.new_hunks.push(Hunk { before, after })
-        })
+        // Most edits (an insertion, a single changed line) only touch a small window of the
+        // file. Trimming the lines that are unchanged at the very start/end before handing the
+        // rest to `imara_diff` shrinks the amount of work `Algorithm::Histogram` has to do
+        // without changing the result: none of the trimmed lines can appear inside a hunk, since
+        // a hunk boundary at the very edge of the file would just be an empty prefix/suffix
+        // match to begin with.
+        let (prefix, suffix) = common_affix(&input.before, &input.after);
+        let before = &input.before[prefix..input.before.len() - suffix];
+        let after = &input.after[prefix..input.after.len() - suffix];
+        let offset = prefix as u32;
+
+        imara_diff::diff_with_tokens(
+            ALGORITHM,
+            before,
+            after,
+            input.interner.num_tokens(),
+            |before: Range<u32>, after: Range<u32>| {
+                self.new_hunks.push(Hunk {
+                    before: before.start + offset..before.end + offset,
+                    after: after.start + offset..after.end + offset,
+                })
+            },
+        )
     }
 }
 
+/// Returns the number of tokens `before` and `after` agree on at the start and at the end,
+/// without double-counting: `common_affix(&[a, a, a], &[a, a])` returns `(2, 0)`, not `(2, 1)`,
+/// since the prefix already accounts for every token `after` has.
+fn common_affix(before: &[imara_diff::intern::Token], after: &[imara_diff::intern::Token]) -> (usize, usize) {
+    let max_prefix = before.len().min(after.len());
+    let prefix = (0..max_prefix)
+        .take_while(|&i| before[i] == after[i])
+        .count();
+
+    let max_suffix = max_prefix - prefix;
+    let suffix = (0..max_suffix)
+        .take_while(|&i| before[before.len() - 1 - i] == after[after.len() - 1 - i])
+        .count();
+
+    (prefix, suffix)
+}
+
+/// Coalesces a burst of [`Event`]s into the single latest value per side (diff base / doc) while
+/// `accumulate_debounced_events` drains the channel during the debounce window, so the worker
+/// only ever diffs against the newest text rather than replaying every intermediate edit.
+///
+/// This is a bespoke, one-off accumulator: this tree has no generic `AsyncHook` trait (debounce
+/// deadline, drain-all helper, or otherwise) for it to implement instead. See the module doc on
+/// `helix-term::job` for the broader "no hook subsystem here" note.
 struct EventAccumulator {
     diff_base: Option<Rope>,
     doc: Option<Rope>,
@@ -1,8 +1,24 @@
 use helix_core::Rope;
+use imara_diff::intern::Token;
 use tokio::task::JoinHandle;
 
+use super::common_affix;
 use crate::diff::{DiffHandle, Hunk};
 
+#[test]
+fn common_affix_does_not_double_count_a_shorter_side() {
+    let a = [Token(0), Token(0), Token(0)];
+    let b = [Token(0), Token(0)];
+    assert_eq!(common_affix(&a, &b), (2, 0));
+}
+
+#[test]
+fn common_affix_finds_a_prefix_and_a_suffix() {
+    let before = [Token(0), Token(1), Token(2), Token(3), Token(4)];
+    let after = [Token(0), Token(1), Token(9), Token(3), Token(4)];
+    assert_eq!(common_affix(&before, &after), (2, 2));
+}
+
 impl DiffHandle {
     fn new_test(diff_base: &str, doc: &str) -> (DiffHandle, JoinHandle<()>) {
         DiffHandle::new_with_handle(
@@ -128,6 +144,99 @@ async fn update_document() {
     )
 }
 
+#[tokio::test]
+async fn subscribers_are_notified_when_a_diff_finishes() {
+    let (differ, handle) = DiffHandle::new_test("foo\n", "foo\n");
+    let subscriber = differ.subscribe();
+    // The worker spawned by `DiffHandle::new_test` only runs once this test yields, so first
+    // wait for its initial diff, then register interest in the *next* notification before
+    // queuing an update - otherwise `notified()` could be created after the worker has already
+    // fired it and miss it.
+    subscriber.notified().await;
+    let notified = subscriber.notified();
+    differ.update_document(Rope::from_str("foo\nbar\n"), false);
+    notified.await;
+
+    let line_diffs = differ.into_diff(handle).await;
+    assert_eq!(
+        &line_diffs,
+        &[Hunk {
+            before: 1..1,
+            after: 1..2
+        }]
+    )
+}
+
+#[tokio::test]
+async fn rapid_successive_updates_only_diff_the_latest() {
+    // Fire several updates back to back with no `.await` in between, so they all land on the
+    // channel before the worker task is ever polled. Only the final document should end up
+    // being diffed - an intermediate result being applied and then immediately overwritten
+    // would still produce the same final hunks, but this also covers the worker skipping a
+    // stale diff if one happened to still be mid-flight when newer updates arrived.
+    let (differ, handle) = DiffHandle::new_test("foo\nbar\ntest\nfoo", "foo\nbar\ntest\nfoo");
+    differ.update_document(Rope::from_str("foo\nbar\ntest\nfoo\nextra"), false);
+    differ.update_document(Rope::from_str("foo\ntest\nfoo"), false);
+    differ.update_document(Rope::from_str("foo\ntest\nfoo bar"), false);
+    let line_diffs = differ.into_diff(handle).await;
+    assert_eq!(
+        &line_diffs,
+        &[
+            Hunk {
+                before: 1..2,
+                after: 1..1
+            },
+            Hunk {
+                before: 3..4,
+                after: 2..3
+            },
+        ]
+    )
+}
+
+#[tokio::test]
+async fn edit_one_line_in_a_large_file() {
+    // Exercises the common-prefix/suffix trimming in `perform_diff`: only line 5000 (of 10000)
+    // differs, so almost the entire file should be trimmed away before `imara_diff` ever runs,
+    // and the reported hunk still needs to point at the right line.
+    let lines: Vec<String> = (0..10_000).map(|i| format!("line {i}")).collect();
+    let base = lines.join("\n");
+    let mut edited = lines.clone();
+    edited[5000] = "line 5000 (edited)".to_string();
+    let doc = edited.join("\n");
+
+    let (differ, handle) = DiffHandle::new_test(&base, &doc);
+    let line_diffs = differ.into_diff(handle).await;
+    assert_eq!(
+        &line_diffs,
+        &[Hunk {
+            before: 5000..5001,
+            after: 5000..5001
+        }]
+    )
+}
+
+#[tokio::test]
+async fn is_modified_flips_true_after_an_edit_and_false_after_reverting_to_base() {
+    let (differ, handle) = DiffHandle::new_test("foo\n", "foo\n");
+    assert!(!differ.is_modified());
+    let subscriber = differ.subscribe();
+    // wait for the worker's initial diff, so the next notification is the edit's own
+    subscriber.notified().await;
+
+    let notified = subscriber.notified();
+    differ.update_document(Rope::from_str("foo\nbar\n"), false);
+    notified.await;
+    assert!(differ.is_modified());
+
+    let notified = subscriber.notified();
+    differ.update_document(Rope::from_str("foo\n"), false);
+    notified.await;
+    assert!(!differ.is_modified());
+
+    differ.into_diff(handle).await;
+}
+
 #[tokio::test]
 async fn update_base() {
     let (differ, handle) = DiffHandle::new_test("foo\ntest\nfoo bar", "foo\ntest\nfoo bar");
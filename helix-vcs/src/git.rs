@@ -1,8 +1,14 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
 use anyhow::{bail, Context, Result};
 use arc_swap::ArcSwap;
-use std::path::Path;
-use std::sync::Arc;
+use imara_diff::intern::InternedInput;
+use imara_diff::Algorithm;
+use parking_lot::RwLock;
 
+use gix::bstr::ByteSlice;
 use gix::objs::tree::EntryMode;
 use gix::sec::trust::DefaultForLevel;
 use gix::{Commit, ObjectId, Repository, ThreadSafeRepository};
@@ -14,7 +20,39 @@
 
 pub struct Git;
 
+/// Directories that repo discovery should never walk above, in addition to whatever ceiling an
+/// individual discovery call passes explicitly. Set process-wide via [`Git::configure`] - e.g.
+/// from the user's global config, to keep discovery from wandering above `$HOME` or into a
+/// mounted network root on every file opened outside of any repo.
+#[derive(Debug, Clone, Default)]
+pub struct RepoCacheConfig {
+    pub ceiling_dirs: Vec<PathBuf>,
+}
+
+fn ceiling_dirs() -> &'static RwLock<Vec<PathBuf>> {
+    static CEILING_DIRS: OnceLock<RwLock<Vec<PathBuf>>> = OnceLock::new();
+    CEILING_DIRS.get_or_init(RwLock::default)
+}
+
+/// Author, commit id and summary for the commit that last touched a single line, as returned by
+/// [`Git::blame_line`].
+#[derive(Debug, Clone)]
+pub struct BlameInfo {
+    pub commit: ObjectId,
+    pub author: String,
+    pub time: gix::date::Time,
+    pub summary: String,
+}
+
 impl Git {
+    /// Sets the process-wide [`RepoCacheConfig`] consulted by every subsequent [`Git::open_repo`]
+    /// call. Overwrites whatever was configured before, so later calls win - unlike
+    /// [`OnceLock::set`], this is meant to be updated (e.g. on a config reload), not set once and
+    /// left alone.
+    pub fn configure(config: RepoCacheConfig) {
+        *ceiling_dirs().write() = config.ceiling_dirs;
+    }
+
     fn open_repo(path: &Path, ceiling_dir: Option<&Path>) -> Result<ThreadSafeRepository> {
         // custom open options
         let mut git_open_opts_map = gix::sec::trust::Mapping::<gix::open::Options>::default();
@@ -43,10 +81,11 @@ fn open_repo(path: &Path, ceiling_dir: Option<&Path>) -> Result<ThreadSafeReposi
             ..gix::open::Permissions::default_for_level(gix::sec::Trust::Full)
         });
 
+        let mut configured_ceiling_dirs = ceiling_dirs().read().clone();
+        configured_ceiling_dirs.extend(ceiling_dir.map(Path::to_owned));
+
         let open_options = gix::discover::upwards::Options {
-            ceiling_dirs: ceiling_dir
-                .map(|dir| vec![dir.to_owned()])
-                .unwrap_or_default(),
+            ceiling_dirs: configured_ceiling_dirs,
             dot_git_only: true,
             ..Default::default()
         };
@@ -59,6 +98,134 @@ fn open_repo(path: &Path, ceiling_dir: Option<&Path>) -> Result<ThreadSafeReposi
 
         Ok(res)
     }
+
+    /// Attributes `line` (0-indexed, counted in the file's current worktree revision) to the
+    /// commit that last changed it, walking first-parent history from `HEAD`.
+    ///
+    /// This is not a full `git blame`: there is no rename detection, and merge commits are
+    /// attributed through their first parent only (the same simplification `git log
+    /// --first-parent` makes), rather than diffing against every parent to find which one
+    /// introduced the change. Between each commit and its first parent, the file's two blobs are
+    /// compared with `imara_diff` - the same line-level differ `diff.rs` uses for the gutter - to
+    /// carry `line` back across the hunks that don't touch it, so the walk can keep following the
+    /// line through unrelated edits elsewhere in the file.
+    ///
+    /// `is_cancelled` is polled once per commit so a caller re-running this on every cursor move
+    /// can abandon a walk over a deep history instead of blocking a redraw on it.
+    pub fn blame_line(
+        file: &Path,
+        mut line: u32,
+        is_cancelled: impl Fn() -> bool,
+    ) -> Result<Option<BlameInfo>> {
+        debug_assert!(file.is_absolute());
+
+        let repo_dir = file.parent().context("file has no parent directory")?;
+        let repo = Git::open_repo(repo_dir, None)
+            .context("failed to open git repo")?
+            .to_thread_local();
+
+        let mut commit = repo.head_commit()?;
+        let mut blob = find_file_in_commit(&repo, &commit, file)?;
+
+        loop {
+            let Some(parent_id) = commit.parent_ids().next() else {
+                break;
+            };
+            if is_cancelled() {
+                return Ok(None);
+            }
+
+            let parent = repo.find_object(parent_id)?.try_into_commit()?;
+            let parent_blob = match find_file_in_commit(&repo, &parent, file) {
+                Ok(oid) => oid,
+                // the file didn't exist in the parent, so `commit` introduced it
+                Err(_) => break,
+            };
+
+            if parent_blob == blob {
+                // untouched between `parent` and `commit`, the change is further back
+                commit = parent;
+                blob = parent_blob;
+                continue;
+            }
+
+            let before = repo.find_object(parent_blob)?.detach().data;
+            let after = repo.find_object(blob)?.detach().data;
+            let (Ok(before), Ok(after)) = (
+                std::str::from_utf8(&before),
+                std::str::from_utf8(&after),
+            ) else {
+                // binary or non-utf8 content: there is no line to carry across, stop here
+                break;
+            };
+
+            let input = InternedInput::new(
+                imara_diff::sources::lines(before),
+                imara_diff::sources::lines(after),
+            );
+            let mut introduced_here = false;
+            let mut shift: i64 = 0;
+            imara_diff::diff(Algorithm::Histogram, &input, |b: Range<u32>, a: Range<u32>| {
+                if introduced_here {
+                    return;
+                }
+                if a.contains(&line) {
+                    introduced_here = true;
+                } else if a.end <= line {
+                    shift += b.len() as i64 - a.len() as i64;
+                }
+            });
+
+            if introduced_here {
+                break;
+            }
+
+            line = (line as i64 + shift) as u32;
+            commit = parent;
+            blob = parent_blob;
+        }
+
+        let author = commit.author()?;
+        let summary = String::from_utf8_lossy(commit.message()?.title.trim()).into_owned();
+        Ok(Some(BlameInfo {
+            commit: commit.id,
+            author: author.name.to_string(),
+            time: author.time,
+            summary,
+        }))
+    }
+
+    /// Reads `file`'s staged blob from the repository's index, or returns `None` if the path has
+    /// no index entry at all (untracked). Like [`DiffProvider::get_diff_base`], the bytes are
+    /// returned undecoded so the caller can apply the document's own encoding before handing them
+    /// to [`crate::DiffHandle::update_diff_base`] - doing that here would risk mis-decoding a
+    /// file whose encoding isn't UTF-8.
+    ///
+    /// A file that has just been `git add`ed but never committed has no `HEAD` blob to speak of,
+    /// but it does have an index entry like any other staged file, so it is not a special case:
+    /// this returns `Some` for it the same as for a file with prior history.
+    pub fn staged_content(file: &Path) -> Result<Option<Vec<u8>>> {
+        debug_assert!(!file.exists() || file.is_file());
+        debug_assert!(file.is_absolute());
+
+        let repo_dir = file.parent().context("file has no parent directory")?;
+        let repo = Git::open_repo(repo_dir, None)
+            .context("failed to open git repo")?
+            .to_thread_local();
+
+        let work_dir = repo.work_dir().context("repo has no worktree")?;
+        let rel_path = file.strip_prefix(work_dir)?;
+        let rel_path = gix::path::into_bstr(rel_path);
+
+        let index = repo.index()?;
+        let entry = match index.entry_by_path_and_stage(&rel_path, 0) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let blob = repo.find_object(entry.id)?.detach().data;
+        Ok(Some(normalize_crlf(blob, &repo)))
+    }
 }
 
 impl DiffProvider for Git {
@@ -76,29 +243,8 @@ fn get_diff_base(&self, file: &Path) -> Result<Vec<u8>> {
         let file_oid = find_file_in_commit(&repo, &head, file)?;
 
         let file_object = repo.find_object(file_oid)?;
-        let mut data = file_object.detach().data;
-        // convert LF to CRLF if configured to avoid showing every line as changed
-        if repo
-            .config_snapshot()
-            .boolean("core.autocrlf")
-            .unwrap_or(false)
-        {
-            let mut normalized_file = Vec::with_capacity(data.len());
-            let mut at_cr = false;
-            for &byte in &data {
-                if byte == b'\n' {
-                    // if this is a LF instead of a CRLF (last byte was not a CR)
-                    // insert a new CR to generate a CRLF
-                    if !at_cr {
-                        normalized_file.push(b'\r');
-                    }
-                }
-                at_cr = byte == b'\r';
-                normalized_file.push(byte)
-            }
-            data = normalized_file
-        }
-        Ok(data)
+        let data = file_object.detach().data;
+        Ok(normalize_crlf(data, &repo))
     }
 
     fn get_current_head_name(&self, file: &Path) -> Result<Arc<ArcSwap<Box<str>>>> {
@@ -120,6 +266,33 @@ fn get_current_head_name(&self, file: &Path) -> Result<Arc<ArcSwap<Box<str>>>> {
     }
 }
 
+/// Converts LF to CRLF line endings if `core.autocrlf` is configured, to avoid a diff base
+/// stored with git's normalized LF endings showing every line of a CRLF worktree file as changed.
+fn normalize_crlf(data: Vec<u8>, repo: &Repository) -> Vec<u8> {
+    if !repo
+        .config_snapshot()
+        .boolean("core.autocrlf")
+        .unwrap_or(false)
+    {
+        return data;
+    }
+
+    let mut normalized_file = Vec::with_capacity(data.len());
+    let mut at_cr = false;
+    for &byte in &data {
+        if byte == b'\n' {
+            // if this is a LF instead of a CRLF (last byte was not a CR)
+            // insert a new CR to generate a CRLF
+            if !at_cr {
+                normalized_file.push(b'\r');
+            }
+        }
+        at_cr = byte == b'\r';
+        normalized_file.push(byte)
+    }
+    normalized_file
+}
+
 /// Finds the object that contains the contents of a file at a specific commit.
 fn find_file_in_commit(repo: &Repository, commit: &Commit, file: &Path) -> Result<ObjectId> {
     let repo_dir = repo.work_dir().context("repo has no worktree")?;
@@ -2,7 +2,12 @@
 
 use tempfile::TempDir;
 
-use crate::{DiffProvider, Git};
+use crate::{DiffProvider, Git, RepoCacheConfig};
+
+fn commit_all(repo: &Path, message: &str) {
+    exec_git_cmd("add -A", repo);
+    exec_git_cmd(&format!("commit -m {message}"), repo);
+}
 
 fn exec_git_cmd(args: &str, git_dir: &Path) {
     let res = Command::new("git")
@@ -119,3 +124,104 @@ fn symlink() {
     assert!(Git.get_diff_base(&file_link).is_err());
     assert_eq!(Git.get_diff_base(&file).unwrap(), Vec::from(contents));
 }
+
+#[test]
+fn blame_line_attributes_a_line_to_the_commit_that_last_touched_it() {
+    let temp_git = empty_git_repo();
+    let file = temp_git.path().join("file.txt");
+    File::create(&file)
+        .unwrap()
+        .write_all(b"line one\nline two\n")
+        .unwrap();
+    commit_all(temp_git.path(), "first");
+
+    File::create(&file)
+        .unwrap()
+        .write_all(b"line one\nline two changed\n")
+        .unwrap();
+    commit_all(temp_git.path(), "second");
+
+    let untouched = Git::blame_line(&file, 0, || false).unwrap().unwrap();
+    assert_eq!(untouched.summary, "first");
+
+    let changed = Git::blame_line(&file, 1, || false).unwrap().unwrap();
+    assert_eq!(changed.summary, "second");
+}
+
+#[test]
+fn staged_content_returns_the_index_blob_ignoring_unstaged_worktree_edits() {
+    let temp_git = empty_git_repo();
+    let file = temp_git.path().join("file.txt");
+    File::create(&file).unwrap().write_all(b"foo").unwrap();
+    commit_all(temp_git.path(), "first");
+
+    File::create(&file).unwrap().write_all(b"bar").unwrap();
+    exec_git_cmd("add file.txt", temp_git.path());
+    File::create(&file).unwrap().write_all(b"baz").unwrap();
+
+    assert_eq!(
+        Git::staged_content(&file).unwrap().unwrap(),
+        Vec::from(b"bar".as_slice())
+    );
+}
+
+#[test]
+fn staged_content_returns_none_for_an_untracked_file() {
+    let temp_git = empty_git_repo();
+    let file = temp_git.path().join("file.txt");
+    File::create(&file).unwrap().write_all(b"foo").unwrap();
+
+    assert!(Git::staged_content(&file).unwrap().is_none());
+}
+
+#[test]
+fn staged_content_returns_the_blob_for_a_newly_added_uncommitted_file() {
+    let temp_git = empty_git_repo();
+    let file = temp_git.path().join("file.txt");
+    File::create(&file).unwrap().write_all(b"foo").unwrap();
+    exec_git_cmd("add file.txt", temp_git.path());
+
+    assert_eq!(
+        Git::staged_content(&file).unwrap().unwrap(),
+        Vec::from(b"foo".as_slice())
+    );
+}
+
+#[test]
+fn configured_ceiling_dir_stops_discovery_from_reaching_an_ancestor_repo() {
+    let outer_repo = empty_git_repo();
+    let ceiling = outer_repo.path().join("nested");
+    let start_dir = ceiling.join("deeper");
+    std::fs::create_dir_all(&start_dir).unwrap();
+    let file = start_dir.join("file.txt");
+    File::create(&file).unwrap().write_all(b"foo").unwrap();
+    create_commit(outer_repo.path(), true);
+
+    // Without a ceiling, discovery walks all the way up and finds the outer repo.
+    assert!(Git.get_diff_base(&file).is_ok());
+
+    Git::configure(RepoCacheConfig {
+        ceiling_dirs: vec![ceiling],
+    });
+    // `nested` is now a boundary discovery may not walk above, so the outer repo - still very
+    // much there - can no longer be found from inside `nested/deeper`.
+    assert!(Git.get_diff_base(&file).is_err());
+
+    // Reset the process-wide config so later tests in this binary aren't affected by this one.
+    Git::configure(RepoCacheConfig::default());
+}
+
+#[test]
+fn blame_line_stops_and_returns_none_when_cancelled() {
+    let temp_git = empty_git_repo();
+    let file = temp_git.path().join("file.txt");
+    File::create(&file).unwrap().write_all(b"foo\n").unwrap();
+    commit_all(temp_git.path(), "first");
+    File::create(&file)
+        .unwrap()
+        .write_all(b"foo\nbar\n")
+        .unwrap();
+    commit_all(temp_git.path(), "second");
+
+    assert!(Git::blame_line(&file, 0, || true).unwrap().is_none());
+}
@@ -3,7 +3,7 @@
 use std::{path::Path, sync::Arc};
 
 #[cfg(feature = "git")]
-pub use git::Git;
+pub use git::{BlameInfo, Git, RepoCacheConfig};
 #[cfg(not(feature = "git"))]
 pub use Dummy as Git;
 
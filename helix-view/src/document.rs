@@ -1473,6 +1473,7 @@ pub fn text_format(&self, mut viewport_width: u16, theme: Option<&Theme>) -> Tex
             soft_wrap: enable_soft_wrap && viewport_width > 10,
             tab_width,
             max_wrap: max_wrap.min(viewport_width / 4),
+            max_wrap_percentage: None,
             max_indent_retain: max_indent_retain.min(viewport_width * 2 / 5),
             // avoid spinning forever when the window manager
             // sets the size to something tiny
@@ -1481,6 +1482,8 @@ pub fn text_format(&self, mut viewport_width: u16, theme: Option<&Theme>) -> Tex
             wrap_indicator_highlight: theme
                 .and_then(|theme| theme.find_scope_index("ui.virtual.wrap"))
                 .map(Highlight),
+            hyphen_break: false,
+            preserve_trailing_whitespace: false,
         }
     }
 
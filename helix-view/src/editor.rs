@@ -5,6 +5,7 @@
     graphics::{CursorKind, Rect},
     info::Info,
     input::KeyEvent,
+    status::StatusHistory,
     theme::{self, Theme},
     tree::{self, Tree},
     view::ViewPosition,
@@ -255,8 +256,28 @@ pub struct Config {
     /// Whether to instruct the LSP to replace the entire word when applying a completion
     /// or to only insert new text
     pub completion_replace: bool,
+    /// Whether accepting a completion item immediately re-triggers completion if the inserted
+    /// text ends in one of the language server's completion trigger characters (e.g. accepting
+    /// `foo.` leaves a fresh request queued for `foo.`'s members). Defaults to false.
+    pub retrigger_after_accept: bool,
+    /// Whether to cluster completion items of the same `CompletionItemKind` together in the
+    /// popup instead of interleaving them in LSP response order. Fuzzy-match score still
+    /// governs ordering within each group. Defaults to false to preserve the existing flat
+    /// ordering.
+    pub completion_group_by_kind: bool,
+    /// Whether to show a status-line message while a completion request is in flight, so slow
+    /// language servers don't leave the editor looking idle. Defaults to false.
+    pub completion_show_loading: bool,
     /// Whether to display infoboxes. Defaults to true.
     pub auto_info: bool,
+    /// Number of recent status-line messages kept for the `:messages` command. Defaults to 20.
+    pub status_history_size: usize,
+    /// Character drawn (styled with `ui.virtual.eof`) at the start of each blank row below the
+    /// end of the document when it's shorter than the viewport, similar to Vim's `~`. Also makes
+    /// rendering those rows self-contained: they're explicitly filled rather than left as
+    /// whatever the surface happened to already contain. `None` (the default) draws nothing,
+    /// preserving the previous behavior of leaving those rows to the caller.
+    pub end_of_buffer_char: Option<char>,
     pub file_picker: FilePickerConfig,
     /// Configuration of the statusline elements
     pub statusline: StatusLineConfig,
@@ -704,6 +725,11 @@ pub struct IndentGuidesConfig {
     pub render: bool,
     pub character: char,
     pub skip_levels: u8,
+    /// Whether to render the outermost (level 0) indent guide, independent of `skip_levels`.
+    /// `None` keeps the existing behavior, where `skip_levels` alone decides it (and horizontal
+    /// scroll can additionally hide it once it scrolls past the left edge, same as any other
+    /// level). Defaults to `None`.
+    pub render_level_0: Option<bool>,
 }
 
 impl Default for IndentGuidesConfig {
@@ -712,6 +738,7 @@ fn default() -> Self {
             skip_levels: 0,
             render: false,
             character: '│',
+            render_level_0: None,
         }
     }
 }
@@ -739,6 +766,8 @@ fn default() -> Self {
             idle_timeout: Duration::from_millis(400),
             completion_trigger_len: 2,
             auto_info: true,
+            status_history_size: 20,
+            end_of_buffer_char: None,
             file_picker: FilePickerConfig::default(),
             statusline: StatusLineConfig::default(),
             cursor_shape: CursorShapeConfig::default(),
@@ -758,6 +787,9 @@ fn default() -> Self {
             },
             text_width: 80,
             completion_replace: false,
+            retrigger_after_accept: false,
+            completion_group_by_kind: false,
+            completion_show_loading: false,
             workspace_lsp_roots: Vec::new(),
         }
     }
@@ -842,6 +874,9 @@ pub struct Editor {
     pub last_selection: Option<Selection>,
 
     pub status_msg: Option<(Cow<'static, str>, Severity)>,
+    /// Recent messages shown via `status_msg`, bounded by `Config::status_history_size` and
+    /// surfaced through the `:messages` command.
+    pub status_history: StatusHistory,
     pub autoinfo: Option<Info>,
 
     pub config: Arc<dyn DynAccess<Config>>,
@@ -881,6 +916,12 @@ pub struct Editor {
     /// field is set and any old requests are automatically
     /// canceled as a result
     pub completion_request_handle: Option<oneshot::Sender<()>>,
+    /// Bumped every time a completion request is sent, regardless of the triggering view/doc.
+    /// A completion callback captures the generation current at trigger time and compares it
+    /// against this field before applying its results, so a request that's still in flight when
+    /// the user re-triggers completion (e.g. by typing quickly) is discarded even though the
+    /// view/doc/mode staleness checks it also runs would otherwise let it through unchanged.
+    pub completion_generation: u64,
 }
 
 pub type RedrawHandle = (Arc<Notify>, Arc<RwLock<()>>);
@@ -968,6 +1009,7 @@ pub fn new(
             registers: Registers::default(),
             clipboard_provider: get_clipboard_provider(),
             status_msg: None,
+            status_history: StatusHistory::default(),
             autoinfo: None,
             idle_timer: Box::pin(sleep(conf.idle_timeout)),
             last_motion: None,
@@ -980,6 +1022,7 @@ pub fn new(
             needs_redraw: false,
             cursor_cache: Cell::new(None),
             completion_request_handle: None,
+            completion_generation: 0,
         }
     }
 
@@ -1023,6 +1066,8 @@ pub fn clear_status(&mut self) {
     pub fn set_status<T: Into<Cow<'static, str>>>(&mut self, status: T) {
         let status = status.into();
         log::debug!("editor status: {}", status);
+        self.status_history
+            .push((status.clone(), Severity::Info), self.config().status_history_size);
         self.status_msg = Some((status, Severity::Info));
     }
 
@@ -1030,6 +1075,8 @@ pub fn set_status<T: Into<Cow<'static, str>>>(&mut self, status: T) {
     pub fn set_error<T: Into<Cow<'static, str>>>(&mut self, error: T) {
         let error = error.into();
         log::error!("editor error: {}", error);
+        self.status_history
+            .push((error.clone(), Severity::Error), self.config().status_history_size);
         self.status_msg = Some((error, Severity::Error));
     }
 
@@ -1038,6 +1085,13 @@ pub fn get_status(&self) -> Option<(&Cow<'static, str>, &Severity)> {
         self.status_msg.as_ref().map(|(status, sev)| (status, sev))
     }
 
+    /// Iterates the recent status-line message history, most recent first. See
+    /// `Config::status_history_size` for how many are retained.
+    #[inline]
+    pub fn status_history(&self) -> impl Iterator<Item = &(Cow<'static, str>, Severity)> {
+        self.status_history.iter()
+    }
+
     /// Returns true if the current status is an error
     #[inline]
     pub fn is_err(&self) -> bool {
@@ -1634,6 +1688,7 @@ pub async fn flush_writes(&mut self) -> anyhow::Result<()> {
                     Ok(event) => event,
                     Err(err) => {
                         self.set_error(err.to_string());
+                        crate::status::report_error(&err);
                         bail!(err);
                     }
                 };
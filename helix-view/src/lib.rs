@@ -15,6 +15,7 @@ pub mod handlers {
 pub mod info;
 pub mod input;
 pub mod keyboard;
+pub mod status;
 pub mod theme;
 pub mod tree;
 pub mod view;
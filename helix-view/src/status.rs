@@ -0,0 +1,94 @@
+//! An installable sink for errors that are otherwise only shown on the status line.
+//!
+//! `Editor::set_error` (and friends) surface failures to the user via the status line, but an
+//! embedder driving Helix headlessly, or a plugin that wants to log failures to a file, has no
+//! way to observe them. [`set_error_sink`] lets such a caller install a callback that receives
+//! the same error, in addition to (not instead of) the status line update.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use once_cell::sync::OnceCell;
+
+use helix_core::diagnostic::Severity;
+
+type ErrorSink = Box<dyn Fn(&anyhow::Error) + Send + Sync>;
+
+static ERROR_SINK: OnceCell<ErrorSink> = OnceCell::new();
+
+/// Installs `sink` to additionally receive every error reported via [`report_error`]. Only the
+/// first call takes effect; later calls are ignored, since there is no way to uninstall a
+/// previously installed sink.
+pub fn set_error_sink(sink: impl Fn(&anyhow::Error) + Send + Sync + 'static) {
+    let _ = ERROR_SINK.set(Box::new(sink));
+}
+
+/// Forwards `err` to the installed error sink, if any. A no-op when no sink has been installed,
+/// so callers can unconditionally call this alongside `Editor::set_error` without checking.
+///
+/// There is no `report_blocking` here to tag with a source module, since that would need the hook/event-dispatch subsystem this tree doesn't have (see the
+/// module doc on `helix-term::job`) to pass an event ID from in the first place.
+pub fn report_error(err: &anyhow::Error) {
+    if let Some(sink) = ERROR_SINK.get() {
+        sink(err);
+    }
+}
+
+/// A single message that was shown on the status line, retained by [`StatusHistory`].
+pub type StatusMessage = (Cow<'static, str>, Severity);
+
+/// A bounded ring buffer of recently shown status-line messages, newest-first.
+///
+/// `Editor::set_status`/`set_error` only keep the single *current* message, so a rapid burst of
+/// messages (e.g. several hook errors) clobbers everything but the last one. This keeps a
+/// separate, bounded history around it (surfaced as `:messages`) without changing what the
+/// statusline itself shows.
+#[derive(Debug, Clone, Default)]
+pub struct StatusHistory {
+    messages: VecDeque<StatusMessage>,
+}
+
+impl StatusHistory {
+    /// Records `message`, dropping the oldest entry first if the history is already at
+    /// `capacity`.
+    ///
+    /// `capacity` is taken per-call rather than fixed at construction, since it comes from
+    /// `Config::status_history_size` and can change on a config reload.
+    pub fn push(&mut self, message: StatusMessage, capacity: usize) {
+        if capacity == 0 {
+            self.messages.clear();
+            return;
+        }
+        while self.messages.len() >= capacity {
+            self.messages.pop_back();
+        }
+        self.messages.push_front(message);
+    }
+
+    /// Iterates the history, most recent message first.
+    pub fn iter(&self) -> impl Iterator<Item = &StatusMessage> {
+        self.messages.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn push_keeps_only_the_most_recent_capacity_messages() {
+        let mut history = StatusHistory::default();
+        for i in 0..5 {
+            history.push((i.to_string().into(), Severity::Info), 3);
+        }
+        let messages: Vec<_> = history.iter().map(|(msg, _)| msg.as_ref()).collect();
+        assert_eq!(messages, ["4", "3", "2"]);
+    }
+
+    #[test]
+    fn push_with_zero_capacity_keeps_no_history() {
+        let mut history = StatusHistory::default();
+        history.push(("first".into(), Severity::Info), 0);
+        assert_eq!(history.iter().count(), 0);
+    }
+}
